@@ -1,4 +1,4 @@
-use impulse::{Body, Real};
+use impulse::{Body, Explosion, ForceGenerator, Gravity, PhysicsWorld, Real};
 use kiss3d::{
     event::{Action, Key, WindowEvent},
     light::Light,
@@ -78,7 +78,9 @@ impl Gun {
         }
     }
 
-    pub fn update(&mut self, last_frame_duration: Real) {
+    /// Moves the rounds, returning where any fireballs burst.
+    pub fn update(&mut self, last_frame_duration: Real) -> Vec<impulse::Vector3> {
+        let mut bursts = Vec::new();
         for round in self.rounds.iter_mut() {
             if round.kind == Shot::Unused {
                 continue;
@@ -94,9 +96,13 @@ impl Gun {
                 None => true,
             };
             if out_of_bounds || expired {
+                if round.kind == Shot::Fireball {
+                    bursts.push(round.body.position);
+                }
                 round.kind = Shot::Unused;
             }
         }
+        bursts
     }
 }
 
@@ -113,6 +119,34 @@ fn main() {
         bullets.push(bullet);
     }
 
+    // A field of debris for fireballs to scatter
+    let mut debris_world = PhysicsWorld::default();
+    let gravity = debris_world.add_force_generator(Gravity::default());
+    debris_world.register_global_force(gravity);
+    for x in -2..=2 {
+        for z in 0..8 {
+            let body = Body::dynamic(5.0)
+                .damping(0.9)
+                .position(impulse::Vector3::new(
+                    x as Real * 1.5,
+                    0.25,
+                    30.0 + z as Real * 1.5,
+                ))
+                .build()
+                .expect("Failed to build debris!");
+            debris_world.add_body(body);
+        }
+    }
+    let mut debris = debris_world
+        .bodies
+        .iter()
+        .map(|_| {
+            let mut piece = window.add_cube(0.5, 0.5, 0.5);
+            piece.set_color(0.8, 0.5, 0.2);
+            piece
+        })
+        .collect::<Vec<_>>();
+
     let mut gun = Gun {
         next_shot_kind: Shot::Pistol,
         ..Default::default()
@@ -134,7 +168,38 @@ fn main() {
 
         // Fake the last frame's duration
         let last_frame_duration = 0.01;
-        gun.update(last_frame_duration);
+        for burst in gun.update(last_frame_duration) {
+            // Fireballs that burst in the air still explode at ground level
+            let detonation = impulse::Vector3::new(burst.x, 0.0, burst.z);
+            let explosion = debris_world.add_force_generator(Explosion::new(detonation));
+            debris_world.register_global_force(explosion);
+        }
+
+        // Clean up explosions once every phase has ended
+        let finished = debris_world
+            .force_generators
+            .iter()
+            .filter(|(_, force_generator)| {
+                let force_generator: &dyn ForceGenerator = force_generator.as_ref();
+                force_generator
+                    .downcast_ref::<Explosion>()
+                    .is_some_and(|explosion| explosion.is_finished())
+            })
+            .map(|(generator_handle, _)| generator_handle)
+            .collect::<Vec<_>>();
+        for generator_handle in finished {
+            debris_world.remove_force_generator(generator_handle);
+        }
+
+        debris_world.tick(last_frame_duration);
+
+        // There are no ground contacts, so keep the debris from sinking
+        for (_body_handle, body) in debris_world.bodies.iter_mut() {
+            if body.position.y < 0.25 {
+                body.position.y = 0.25;
+                body.velocity.y = body.velocity.y.max(0.0);
+            }
+        }
 
         window.draw_text(
             &format!("Current Ammo Type: {:?}", gun.next_shot_kind),
@@ -152,6 +217,14 @@ fn main() {
             );
         }
 
+        for ((_body_handle, body), piece) in debris_world.bodies.iter().zip(debris.iter_mut()) {
+            piece.set_local_translation(Translation3::new(
+                body.position.x,
+                body.position.y,
+                body.position.z,
+            ));
+        }
+
         for (round, bullet) in gun.rounds.iter().zip(bullets.iter_mut()) {
             let is_used = round.kind != Shot::Unused;
             bullet.set_visible(is_used);
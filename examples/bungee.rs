@@ -1,4 +1,4 @@
-use impulse::{AnchoredBungee, Body, ForceRegistration, Gravity, PhysicsWorld};
+use impulse::{AnchoredBungee, Body, Gravity, PhysicsWorld};
 use kiss3d::{
     camera::ArcBall,
     event::{Action, Key, WindowEvent},
@@ -25,39 +25,30 @@ fn main() {
     let mut physics_world = PhysicsWorld::default();
 
     // Register forces
-    let gravity = physics_world
-        .force_generators
-        .insert(Box::new(Gravity::default()));
+    let gravity = physics_world.add_force_generator(Gravity::default());
+    physics_world.register_global_force(gravity);
 
     let anchor_height = 10.0;
-    let anchored_bungee = physics_world
-        .force_generators
-        .insert(Box::new(AnchoredBungee {
-            anchor: impulse::Vector3::new(0.0, anchor_height, 0.0),
-            spring_constant: 4.0,
-            rest_length: 2.0,
-        }));
+    let anchored_bungee = physics_world.add_force_generator(AnchoredBungee {
+        anchor: impulse::Vector3::new(0.0, anchor_height, 0.0),
+        spring_constant: 4.0,
+        rest_length: 2.0,
+    });
 
     // Add bungee anchor point visual
     let mut anchor = window.add_cube(1.0, 1.0, 1.0);
     anchor.set_local_translation(Translation3::new(0.0, anchor_height, 0.0));
 
     // Register bodies
-    let body_handle = physics_world.bodies.insert(Body {
-        inverse_mass: 2.0_f32.recip(),
-        damping: 0.99,
-        position: impulse::Vector3::new(-2.0, 8.0, 3.0),
-        ..Default::default()
-    });
-
-    // Link forces to all bodies
-    physics_world
-        .registrations
-        .push(ForceRegistration::new(gravity, vec![body_handle]));
-
-    physics_world
-        .registrations
-        .push(ForceRegistration::new(anchored_bungee, vec![body_handle]));
+    let body = Body::dynamic(2.0)
+        .damping(0.99)
+        .position(impulse::Vector3::new(-2.0, 8.0, 3.0))
+        .build()
+        .expect("Failed to build body!");
+    let body_handle = physics_world.add_body(body);
+
+    // Link forces to the body
+    physics_world.register_force(anchored_bungee, body_handle);
 
     let mut spheres = Vec::new();
 
@@ -1,4 +1,4 @@
-use impulse::{Body, ForceRegistration, Gravity, PhysicsWorld};
+use impulse::{Body, Gravity, PhysicsWorld};
 use kiss3d::{
     camera::ArcBall,
     event::{Action, Key, WindowEvent},
@@ -25,22 +25,16 @@ fn main() {
     let mut physics_world = PhysicsWorld::default();
 
     // Register forces
-    let gravity = physics_world
-        .force_generators
-        .insert(Box::new(Gravity::default()));
+    let gravity = physics_world.add_force_generator(Gravity::default());
+    physics_world.register_global_force(gravity);
 
     // Register bodies
-    let body_handle = physics_world.bodies.insert(Body {
-        inverse_mass: 2.0_f32.recip(),
-        damping: 0.99,
-        position: impulse::Vector3::new(0.0, 8.0, 0.0),
-        ..Default::default()
-    });
-
-    // Link forces to all bodies
-    physics_world
-        .registrations
-        .push(ForceRegistration::new(gravity, vec![body_handle]));
+    let body = Body::dynamic(2.0)
+        .damping(0.99)
+        .position(impulse::Vector3::new(0.0, 8.0, 0.0))
+        .build()
+        .expect("Failed to build body!");
+    physics_world.add_body(body);
 
     let mut spheres = Vec::new();
 
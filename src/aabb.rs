@@ -0,0 +1,136 @@
+use crate::{Real, Vector3};
+
+/// An axis-aligned bounding box.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3,
+    pub max: Vector3,
+}
+
+impl Aabb {
+    pub fn new(min: Vector3, max: Vector3) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_center(center: Vector3, half_extents: Vector3) -> Self {
+        Self::new(center - half_extents, center + half_extents)
+    }
+
+    pub fn from_sphere(center: Vector3, radius: Real) -> Self {
+        Self::from_center(center, Vector3::new(radius, radius, radius))
+    }
+
+    pub fn center(&self) -> Vector3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_extents(&self) -> Vector3 {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn surface_area(&self) -> Real {
+        let size = self.max - self.min;
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+
+    /// Grows the box by the given margin on every side.
+    pub fn expand(&self, margin: Real) -> Self {
+        let margin = Vector3::new(margin, margin, margin);
+        Self::new(self.min - margin, self.max + margin)
+    }
+
+    /// The smallest box containing both boxes.
+    pub fn merge(&self, other: &Self) -> Self {
+        Self::new(
+            Vector3::new(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+            ),
+            Vector3::new(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+            ),
+        )
+    }
+
+    pub fn contains(&self, other: &Self) -> bool {
+        self.min.x <= other.min.x
+            && self.min.y <= other.min.y
+            && self.min.z <= other.min.z
+            && self.max.x >= other.max.x
+            && self.max.y >= other.max.y
+            && self.max.z >= other.max.z
+    }
+
+    pub fn contains_point(&self, point: Vector3) -> bool {
+        self.min.x <= point.x
+            && self.min.y <= point.y
+            && self.min.z <= point.z
+            && self.max.x >= point.x
+            && self.max.y >= point.y
+            && self.max.z >= point.z
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x
+            && self.min.y <= other.max.y
+            && self.min.z <= other.max.z
+            && self.max.x >= other.min.x
+            && self.max.y >= other.min.y
+            && self.max.z >= other.min.z
+    }
+
+    /// The point inside the box closest to the given point.
+    pub fn closest_point(&self, point: Vector3) -> Vector3 {
+        Vector3::new(
+            point.x.max(self.min.x).min(self.max.x),
+            point.y.max(self.min.y).min(self.max.y),
+            point.z.max(self.min.z).min(self.max.z),
+        )
+    }
+
+    pub fn distance_squared_to_point(&self, point: Vector3) -> Real {
+        (self.closest_point(point) - point).magnitude_squared()
+    }
+
+    /// Finds the distance at which a ray enters the box using the slab method.
+    /// Rays starting inside the box hit at a distance of zero.
+    pub fn cast_ray(
+        &self,
+        origin: Vector3,
+        direction: Vector3,
+        max_distance: Real,
+    ) -> Option<Real> {
+        let mut entry: Real = 0.0;
+        let mut exit = max_distance;
+
+        let axes = [
+            (origin.x, direction.x, self.min.x, self.max.x),
+            (origin.y, direction.y, self.min.y, self.max.y),
+            (origin.z, direction.z, self.min.z, self.max.z),
+        ];
+
+        for (origin, direction, min, max) in axes.iter().copied() {
+            if direction.abs() <= Real::EPSILON {
+                // The ray is parallel to this slab
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+
+            let inverse_direction = direction.recip();
+            let near = (min - origin) * inverse_direction;
+            let far = (max - origin) * inverse_direction;
+            entry = entry.max(near.min(far));
+            exit = exit.min(near.max(far));
+            if entry > exit {
+                return None;
+            }
+        }
+
+        Some(entry)
+    }
+}
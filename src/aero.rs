@@ -0,0 +1,196 @@
+use crate::{Body, BodyHandle, BodySet, ForceContext, ForceGenerator, Matrix3, Real, Vector3};
+use std::any::Any;
+
+/// Applies aerodynamic forces, such as the lift and drag of a wing, from a tensor
+/// that maps a body's velocity through the air to the force on it.
+///
+/// The tensor and the point the force acts through are both in body space, so the
+/// force turns with the body. Unless the point is the body's center, the force also
+/// produces a torque, which only affects bodies with an inertia tensor.
+pub struct Aero {
+    /// Maps the body's velocity relative to the air to the aerodynamic force, in body space
+    pub tensor: Matrix3,
+
+    /// The point the force acts through, in body space
+    pub position: Vector3,
+
+    /// The velocity of the air
+    pub wind: Vector3,
+}
+
+impl Aero {
+    pub fn new(tensor: Matrix3, position: Vector3) -> Self {
+        Self {
+            tensor,
+            position,
+            wind: Vector3::zero(),
+        }
+    }
+
+    /// Applies the aerodynamic force produced by the given tensor to a body.
+    fn apply_tensor(tensor: &Matrix3, position: Vector3, wind: Vector3, body: &mut Body) {
+        let velocity = body.direction_to_body(body.velocity - wind);
+        let force = body.direction_to_world(*tensor * velocity);
+        body.add_force_at_body_point(&force, position);
+    }
+}
+
+impl ForceGenerator for Aero {
+    fn apply(&mut self, _context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        if let Some(body) = bodies.get_mut(body_handle) {
+            Self::apply_tensor(&self.tensor, self.position, self.wind, body);
+        }
+    }
+}
+
+/// An aerodynamic surface whose tensor can be adjusted by a control input,
+/// such as a rudder or an aileron.
+///
+/// Like `Aero`, the tensor and the point the force acts through are in body space.
+pub struct AeroControl {
+    /// The tensor when the control is at its neutral position of zero
+    pub tensor: Matrix3,
+
+    /// The tensor when the control is fully at -1
+    pub min_tensor: Matrix3,
+
+    /// The tensor when the control is fully at 1
+    pub max_tensor: Matrix3,
+
+    /// The point the force acts through, in body space
+    pub position: Vector3,
+
+    /// The velocity of the air
+    pub wind: Vector3,
+
+    control: Real,
+}
+
+impl AeroControl {
+    pub fn new(
+        tensor: Matrix3,
+        min_tensor: Matrix3,
+        max_tensor: Matrix3,
+        position: Vector3,
+    ) -> Self {
+        Self {
+            tensor,
+            min_tensor,
+            max_tensor,
+            position,
+            wind: Vector3::zero(),
+            control: 0.0,
+        }
+    }
+
+    pub fn control(&self) -> Real {
+        self.control
+    }
+
+    /// Moves the control surface, clamped to the range -1 to 1.
+    pub fn set_control(&mut self, control: Real) {
+        self.control = control.clamp(-1.0, 1.0);
+    }
+
+    /// The tensor for the current control input.
+    pub fn current_tensor(&self) -> Matrix3 {
+        if self.control < 0.0 {
+            self.tensor.lerp(&self.min_tensor, -self.control)
+        } else {
+            self.tensor.lerp(&self.max_tensor, self.control)
+        }
+    }
+}
+
+impl ForceGenerator for AeroControl {
+    fn apply(&mut self, _context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        let tensor = self.current_tensor();
+        if let Some(body) = bodies.get_mut(body_handle) {
+            Aero::apply_tensor(&tensor, self.position, self.wind, body);
+        }
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PhysicsWorld, Quaternion, Shape};
+    use std::f32::consts::FRAC_PI_2;
+
+    fn force_on(aero: &mut Aero, body: Body) -> (Vector3, Vector3) {
+        let mut bodies = BodySet::new();
+        let body = bodies.insert(body);
+        aero.apply(&ForceContext::default(), body, &mut bodies);
+        (
+            bodies[body].force_accumulator,
+            bodies[body].torque_accumulator,
+        )
+    }
+
+    #[test]
+    fn tensor_turns_with_the_body() {
+        // Only resists motion along the body's x axis
+        let mut aero = Aero::new(
+            Matrix3::diagonal(Vector3::new(-2.0, 0.0, 0.0)),
+            Vector3::zero(),
+        );
+
+        // Turned a quarter about y, the body's x axis points along world -z
+        let orientation = Quaternion::from_axis_angle(Vector3::y(), FRAC_PI_2);
+        let body = |velocity| {
+            Body::dynamic(1.0)
+                .orientation(orientation)
+                .velocity(velocity)
+                .build()
+                .unwrap()
+        };
+
+        let (force, torque) = force_on(&mut aero, body(Vector3::new(0.0, 0.0, 3.0)));
+        assert!((force - Vector3::new(0.0, 0.0, -6.0)).magnitude() < 1e-5);
+        assert!(torque.magnitude() < 1e-5);
+
+        let (force, _) = force_on(&mut aero, body(Vector3::new(3.0, 0.0, 0.0)));
+        assert!(force.magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn offset_force_turns_the_body() {
+        // A wing on the body's right that lifts in proportion to forward speed
+        let lift = Matrix3::new([
+            0.0, 0.0, 0.0, //
+            0.0, 0.0, 2.0, //
+            0.0, 0.0, 0.0,
+        ]);
+        let mut aero = Aero::new(lift, Vector3::new(1.0, 0.0, 0.0));
+        let body = Body::dynamic(1.0)
+            .shape(Shape::sphere(1.0))
+            .velocity(Vector3::new(0.0, 0.0, 1.0))
+            .build()
+            .unwrap();
+
+        let (force, torque) = force_on(&mut aero, body);
+        assert!((force - Vector3::new(0.0, 2.0, 0.0)).magnitude() < 1e-5);
+        assert!((torque - Vector3::new(0.0, 0.0, 2.0)).magnitude() < 1e-5);
+
+        // In a world, the torque rolls the body about its forward axis
+        let mut world = PhysicsWorld::default();
+        let wing = world.add_force_generator(aero);
+        let body = world.add_body(body);
+        world.register_force(wing, body);
+        world.tick(0.1);
+        world.tick(0.1);
+
+        let body = &world.bodies[body];
+        assert!(body.angular_velocity.z > 0.0);
+        let right = body.direction_to_world(Vector3::x());
+        assert!(right.y > 0.0);
+    }
+}
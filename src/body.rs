@@ -1,7 +1,26 @@
-use crate::{Real, Vector3};
+use crate::{CollisionFilter, Matrix3, Quaternion, Real, Shape, Vector3};
+use std::fmt;
 
-#[derive(Debug, Default, Copy, Clone)]
+/// Determines how a body is moved by the simulation.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum BodyType {
+    /// Moved by forces and contacts
+    #[default]
+    Dynamic,
+
+    /// Never moves
+    Static,
+
+    /// Moved only by its velocity or kinematic target, ignoring forces.
+    /// Kinematic bodies behave as if they had infinite mass in contacts,
+    /// pushing dynamic bodies along with their velocity.
+    Kinematic,
+}
+
+#[derive(Debug, Copy, Clone)]
 pub struct Body {
+    pub body_type: BodyType,
+
     pub position: Vector3,
     pub velocity: Vector3,
     pub acceleration: Vector3,
@@ -31,9 +50,102 @@ pub struct Body {
     // simulation iteration only. This value is zeroed at each
     // integration step.
     pub force_accumulator: Vector3,
+
+    /// The geometry of the body used by spatial queries.
+    /// Defaults to a point particle.
+    pub shape: Shape,
+
+    /// Controls which other bodies this body may interact with.
+    /// By default bodies interact with everything.
+    pub collision_filter: CollisionFilter,
+
+    /// The volume of the body in cubic meters when its shape has none, such as a point particle.
+    /// Point particles behave like a sphere of this volume when submerged.
+    pub point_volume: Real,
+
+    /// How streamlined the body is, which scales the aerodynamic drag on it.
+    /// Defaults to the drag coefficient of a sphere.
+    pub drag_coefficient: Real,
+
+    /// The area the body presents to oncoming air or water in square meters,
+    /// replacing the silhouette of its shape when set.
+    pub cross_sectional_area: Option<Real>,
+
+    /// Scales the pull of gravity generators on the body.
+    /// Zero makes the body ignore gravity and negative values make it float upwards.
+    pub gravity_scale: Real,
+
+    /// The electric charge of the body in coulombs, used by electromagnetic force generators
+    pub charge: Real,
+
+    /// The position a kinematic body should reach by the end of the next tick.
+    /// While set, the body's velocity is derived from it on every tick.
+    pub kinematic_target: Option<Vector3>,
+
+    /// The rotation from body space to world space
+    pub orientation: Quaternion,
+
+    /// The axis the body spins around, scaled by its speed in radians per second
+    pub angular_velocity: Vector3,
+
+    /// The amount of damping applied to angular motion, like `damping` is for linear motion
+    pub angular_damping: Real,
+
+    /// The inverse of the body's inertia tensor in body space.
+    ///
+    /// Like the inverse mass, zero means the body can't be turned, which is
+    /// how point particles behave.
+    pub inverse_inertia_tensor: Matrix3,
+
+    // Holds the accumulated torque to be applied at the next
+    // simulation iteration only. This value is zeroed at each
+    // integration step.
+    pub torque_accumulator: Vector3,
+}
+
+impl Default for Body {
+    fn default() -> Self {
+        Self {
+            body_type: BodyType::default(),
+            position: Vector3::zero(),
+            velocity: Vector3::zero(),
+            acceleration: Vector3::zero(),
+            damping: 0.0,
+            inverse_mass: 0.0,
+            force_accumulator: Vector3::zero(),
+            shape: Shape::default(),
+            collision_filter: CollisionFilter::default(),
+            point_volume: 1e-3,
+            drag_coefficient: 0.47,
+            cross_sectional_area: None,
+            gravity_scale: 1.0,
+            charge: 0.0,
+            kinematic_target: None,
+            orientation: Quaternion::identity(),
+            angular_velocity: Vector3::zero(),
+            angular_damping: 0.0,
+            inverse_inertia_tensor: Matrix3::zero(),
+            torque_accumulator: Vector3::zero(),
+        }
+    }
 }
 
 impl Body {
+    /// Starts building a dynamic body with the given mass in kilograms.
+    pub fn dynamic(mass: Real) -> BodyBuilder {
+        BodyBuilder::new(BodyType::Dynamic).mass(mass)
+    }
+
+    /// Starts building a static body, which never moves.
+    pub fn fixed() -> BodyBuilder {
+        BodyBuilder::new(BodyType::Static)
+    }
+
+    /// Starts building a kinematic body, which is moved only by its velocity.
+    pub fn kinematic() -> BodyBuilder {
+        BodyBuilder::new(BodyType::Kinematic)
+    }
+
     pub fn mass(&self) -> Real {
         self.inverse_mass.recip()
     }
@@ -42,15 +154,104 @@ impl Body {
         self.inverse_mass == 0.0
     }
 
+    /// The volume enclosed by the body, which is the point volume for point particles.
+    pub fn volume(&self) -> Real {
+        match self.shape {
+            Shape::Sphere { radius } if radius <= 0.0 => self.point_volume,
+            shape => shape.volume(),
+        }
+    }
+
+    /// The radius of the body's sphere, or of a sphere with the point volume for
+    /// point particles. Planes have an infinite radius.
+    pub fn equivalent_radius(&self) -> Real {
+        match self.shape {
+            Shape::Sphere { radius } if radius <= 0.0 => {
+                (0.75 * self.point_volume / std::f32::consts::PI).cbrt()
+            }
+            Shape::Sphere { radius } => radius,
+            Shape::Plane { .. } => Real::INFINITY,
+        }
+    }
+
+    /// The area the body presents to oncoming air or water. Without an explicit
+    /// cross-sectional area this is the silhouette of its equivalent sphere.
+    pub fn frontal_area(&self) -> Real {
+        match self.cross_sectional_area {
+            Some(area) => area,
+            None => std::f32::consts::PI * self.equivalent_radius().powi(2),
+        }
+    }
+
+    /// The inverse mass used when resolving contacts.
+    /// Static and kinematic bodies can't be pushed, so they act as if their mass were infinite.
+    pub fn effective_inverse_mass(&self) -> Real {
+        match self.body_type {
+            BodyType::Dynamic => self.inverse_mass,
+            BodyType::Static | BodyType::Kinematic => 0.0,
+        }
+    }
+
     pub fn add_force(&mut self, force: &Vector3) {
         self.force_accumulator += force;
     }
 
+    pub fn add_torque(&mut self, torque: &Vector3) {
+        self.torque_accumulator += torque;
+    }
+
+    /// Adds a force acting through a point in world space, which turns
+    /// the body unless the force points through its center.
+    pub fn add_force_at_point(&mut self, force: &Vector3, point: Vector3) {
+        let arm = point - self.position;
+        self.add_force(force);
+        self.add_torque(&arm.cross(*force));
+    }
+
+    /// Adds a force acting through a point given in body space.
+    pub fn add_force_at_body_point(&mut self, force: &Vector3, point: Vector3) {
+        self.add_force_at_point(force, self.point_to_world(point));
+    }
+
+    /// Converts a point in body space to world space.
+    pub fn point_to_world(&self, point: Vector3) -> Vector3 {
+        self.position + self.orientation.rotate(point)
+    }
+
+    /// Converts a direction in body space to world space.
+    pub fn direction_to_world(&self, direction: Vector3) -> Vector3 {
+        self.orientation.rotate(direction)
+    }
+
+    /// Converts a direction in world space to body space.
+    pub fn direction_to_body(&self, direction: Vector3) -> Vector3 {
+        self.orientation.conjugate().rotate(direction)
+    }
+
+    /// The angular acceleration a torque in world space would cause.
+    pub fn angular_acceleration(&self, torque: Vector3) -> Vector3 {
+        let body_torque = self.direction_to_body(torque);
+        self.direction_to_world(self.inverse_inertia_tensor * body_torque)
+    }
+
     /// Integrates the body forward in time by the given amount.
     /// This function uses a Newton-Euler integration method, which is a
     /// linear approximation to the correct integral. For this reason it
     /// may be inaccurate in some cases.
     pub fn integrate(&mut self, duration: Real) {
+        match self.body_type {
+            BodyType::Dynamic => {}
+            BodyType::Static => {
+                self.force_accumulator = Vector3::zero();
+                self.torque_accumulator = Vector3::zero();
+                return;
+            }
+            BodyType::Kinematic => {
+                self.integrate_kinematic(duration);
+                return;
+            }
+        }
+
         if self.inverse_mass <= 0.0 {
             return;
         }
@@ -70,5 +271,407 @@ impl Body {
 
         // Clear any accumulated forces
         self.force_accumulator = Vector3::zero();
+
+        self.integrate_rotation(duration);
+    }
+
+    /// Turns the body by its angular velocity and updates the angular velocity from
+    /// the accumulated torque, in the same order as the linear motion.
+    fn integrate_rotation(&mut self, duration: Real) {
+        self.orientation = self
+            .orientation
+            .integrate(self.angular_velocity, duration)
+            .normalize();
+
+        self.angular_velocity += self.angular_acceleration(self.torque_accumulator) * duration;
+        self.angular_velocity *= self.angular_damping.powf(duration);
+
+        self.torque_accumulator = Vector3::zero();
+    }
+
+    /// Moves a kinematic body by its velocity, steering it
+    /// towards its kinematic target if it has one.
+    fn integrate_kinematic(&mut self, duration: Real) {
+        // FIXME: Return a real error here instead of panicking
+        assert!(duration > 0.0);
+
+        if let Some(target) = self.kinematic_target {
+            self.velocity = (target - self.position) / duration;
+        }
+
+        self.position += self.velocity * duration;
+        self.orientation = self
+            .orientation
+            .integrate(self.angular_velocity, duration)
+            .normalize();
+
+        // Kinematic bodies ignore forces
+        self.force_accumulator = Vector3::zero();
+        self.torque_accumulator = Vector3::zero();
+    }
+}
+
+/// The reasons a body can fail to be built.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BodyError {
+    /// The mass of a dynamic body must be positive and finite
+    InvalidMass(Real),
+
+    /// Density must be positive and finite
+    InvalidDensity(Real),
+
+    /// Volumes, including those mass is derived from, must be positive and finite
+    InvalidVolume(Real),
+
+    /// Damping must lie between zero and one
+    InvalidDamping(Real),
+
+    /// A cross-sectional area must be finite and not negative
+    InvalidArea(Real),
+
+    /// The radius of a sphere must be finite and not negative
+    InvalidRadius(Real),
+
+    /// The principal moments of inertia must be positive and finite
+    InvalidInertia(Vector3),
+
+    /// The named property contains an infinite or NaN component
+    NonFinite(&'static str),
+}
+
+impl fmt::Display for BodyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidMass(mass) => write!(f, "mass must be positive and finite, got {}", mass),
+            Self::InvalidDensity(density) => {
+                write!(f, "density must be positive and finite, got {}", density)
+            }
+            Self::InvalidVolume(volume) => {
+                write!(f, "volume must be positive and finite, got {}", volume)
+            }
+            Self::InvalidDamping(damping) => {
+                write!(f, "damping must be between 0 and 1, got {}", damping)
+            }
+            Self::InvalidArea(area) => {
+                write!(
+                    f,
+                    "cross-sectional area must be finite and not negative, got {}",
+                    area
+                )
+            }
+            Self::InvalidRadius(radius) => {
+                write!(f, "radius must be finite and not negative, got {}", radius)
+            }
+            Self::InvalidInertia(inertia) => write!(
+                f,
+                "moments of inertia must be positive and finite, got {:?}",
+                inertia
+            ),
+            Self::NonFinite(property) => write!(f, "{} must be finite", property),
+        }
+    }
+}
+
+impl std::error::Error for BodyError {}
+
+#[derive(Debug, Copy, Clone)]
+enum MassSource {
+    Mass(Real),
+    Density(Real),
+}
+
+/// Builds a body with sensible defaults, validating its properties.
+///
+/// Built bodies use a damping of 0.999 unless told otherwise,
+/// which removes numerical instability without visibly slowing the body.
+#[derive(Debug, Copy, Clone)]
+pub struct BodyBuilder {
+    body: Body,
+    mass: Option<MassSource>,
+    inertia: Option<Vector3>,
+}
+
+impl BodyBuilder {
+    pub fn new(body_type: BodyType) -> Self {
+        Self {
+            body: Body {
+                body_type,
+                damping: 0.999,
+                angular_damping: 0.999,
+                ..Default::default()
+            },
+            mass: None,
+            inertia: None,
+        }
+    }
+
+    pub fn mass(mut self, mass: Real) -> Self {
+        self.mass = Some(MassSource::Mass(mass));
+        self
+    }
+
+    /// Derives the mass from the volume of the body's shape instead of setting it directly.
+    pub fn density(mut self, density: Real) -> Self {
+        self.mass = Some(MassSource::Density(density));
+        self
+    }
+
+    pub fn position(mut self, position: Vector3) -> Self {
+        self.body.position = position;
+        self
+    }
+
+    pub fn velocity(mut self, velocity: Vector3) -> Self {
+        self.body.velocity = velocity;
+        self
+    }
+
+    pub fn acceleration(mut self, acceleration: Vector3) -> Self {
+        self.body.acceleration = acceleration;
+        self
+    }
+
+    pub fn damping(mut self, damping: Real) -> Self {
+        self.body.damping = damping;
+        self
+    }
+
+    pub fn orientation(mut self, orientation: Quaternion) -> Self {
+        self.body.orientation = orientation;
+        self
+    }
+
+    pub fn angular_velocity(mut self, angular_velocity: Vector3) -> Self {
+        self.body.angular_velocity = angular_velocity;
+        self
+    }
+
+    pub fn angular_damping(mut self, angular_damping: Real) -> Self {
+        self.body.angular_damping = angular_damping;
+        self
+    }
+
+    /// Sets the principal moments of inertia about the body's axes.
+    ///
+    /// Dynamic spheres otherwise get the inertia of a solid sphere,
+    /// while point particles can't be turned.
+    pub fn inertia(mut self, inertia: Vector3) -> Self {
+        self.inertia = Some(inertia);
+        self
+    }
+
+    pub fn shape(mut self, shape: Shape) -> Self {
+        self.body.shape = shape;
+        self
+    }
+
+    pub fn collision_filter(mut self, collision_filter: CollisionFilter) -> Self {
+        self.body.collision_filter = collision_filter;
+        self
+    }
+
+    pub fn point_volume(mut self, point_volume: Real) -> Self {
+        self.body.point_volume = point_volume;
+        self
+    }
+
+    pub fn drag_coefficient(mut self, drag_coefficient: Real) -> Self {
+        self.body.drag_coefficient = drag_coefficient;
+        self
+    }
+
+    pub fn cross_sectional_area(mut self, cross_sectional_area: Real) -> Self {
+        self.body.cross_sectional_area = Some(cross_sectional_area);
+        self
+    }
+
+    pub fn gravity_scale(mut self, gravity_scale: Real) -> Self {
+        self.body.gravity_scale = gravity_scale;
+        self
+    }
+
+    pub fn charge(mut self, charge: Real) -> Self {
+        self.body.charge = charge;
+        self
+    }
+
+    pub fn build(self) -> Result<Body, BodyError> {
+        let mut body = self.body;
+
+        let vectors = [
+            ("position", body.position),
+            ("velocity", body.velocity),
+            ("acceleration", body.acceleration),
+            ("angular velocity", body.angular_velocity),
+        ];
+        if let Some((property, _)) = vectors.iter().find(|(_, vector)| !vector.is_finite()) {
+            return Err(BodyError::NonFinite(property));
+        }
+
+        match body.shape {
+            Shape::Sphere { radius } => {
+                if !(radius.is_finite() && radius >= 0.0) {
+                    return Err(BodyError::InvalidRadius(radius));
+                }
+            }
+            Shape::Plane { normal } => {
+                if !normal.is_finite() {
+                    return Err(BodyError::NonFinite("plane normal"));
+                }
+            }
+        }
+
+        if !(body.point_volume.is_finite() && body.point_volume > 0.0) {
+            return Err(BodyError::InvalidVolume(body.point_volume));
+        }
+
+        if !body.drag_coefficient.is_finite() {
+            return Err(BodyError::NonFinite("drag coefficient"));
+        }
+
+        if let Some(area) = body.cross_sectional_area {
+            if !(area.is_finite() && area >= 0.0) {
+                return Err(BodyError::InvalidArea(area));
+            }
+        }
+
+        if !body.gravity_scale.is_finite() {
+            return Err(BodyError::NonFinite("gravity scale"));
+        }
+
+        if !body.charge.is_finite() {
+            return Err(BodyError::NonFinite("charge"));
+        }
+
+        if !(0.0..=1.0).contains(&body.damping) {
+            return Err(BodyError::InvalidDamping(body.damping));
+        }
+
+        if !(0.0..=1.0).contains(&body.angular_damping) {
+            return Err(BodyError::InvalidDamping(body.angular_damping));
+        }
+
+        if !body.orientation.is_finite() || body.orientation.magnitude() <= 0.0 {
+            return Err(BodyError::NonFinite("orientation"));
+        }
+        body.orientation = body.orientation.normalize();
+
+        let mass = match self.mass {
+            Some(MassSource::Mass(mass)) => mass,
+            Some(MassSource::Density(density)) => {
+                if !(density.is_finite() && density > 0.0) {
+                    return Err(BodyError::InvalidDensity(density));
+                }
+                let volume = body.shape.volume();
+                if !(volume.is_finite() && volume > 0.0) {
+                    return Err(BodyError::InvalidVolume(volume));
+                }
+                density * volume
+            }
+            None => Real::INFINITY,
+        };
+
+        body.inverse_mass = match body.body_type {
+            BodyType::Dynamic => {
+                if !(mass.is_finite() && mass > 0.0) {
+                    return Err(BodyError::InvalidMass(mass));
+                }
+                mass.recip()
+            }
+            BodyType::Static | BodyType::Kinematic => 0.0,
+        };
+
+        let inertia = match (self.inertia, body.shape) {
+            (Some(inertia), _) => Some(inertia),
+            (None, Shape::Sphere { radius }) if radius > 0.0 => {
+                let moment = 0.4 * mass * radius.powi(2);
+                Some(Vector3::new(moment, moment, moment))
+            }
+            (None, _) => None,
+        };
+
+        body.inverse_inertia_tensor = match (body.body_type, inertia) {
+            (BodyType::Dynamic, Some(inertia)) => {
+                let valid = |moment: Real| moment.is_finite() && moment > 0.0;
+                if !(valid(inertia.x) && valid(inertia.y) && valid(inertia.z)) {
+                    return Err(BodyError::InvalidInertia(inertia));
+                }
+                Matrix3::diagonal(Vector3::new(1.0, 1.0, 1.0) / inertia)
+            }
+            _ => Matrix3::zero(),
+        };
+
+        Ok(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_rejects_invalid_properties() {
+        let invalid = [
+            (Body::dynamic(0.0), BodyError::InvalidMass(0.0)),
+            (Body::dynamic(-2.0), BodyError::InvalidMass(-2.0)),
+            (
+                Body::dynamic(1.0).damping(1.5),
+                BodyError::InvalidDamping(1.5),
+            ),
+            (
+                Body::dynamic(1.0).shape(Shape::sphere(-3.0)),
+                BodyError::InvalidRadius(-3.0),
+            ),
+            (
+                Body::dynamic(1.0).shape(Shape::sphere(Real::INFINITY)),
+                BodyError::InvalidRadius(Real::INFINITY),
+            ),
+            (
+                Body::dynamic(1.0).velocity(Vector3::new(0.0, Real::NAN, 0.0)),
+                BodyError::NonFinite("velocity"),
+            ),
+            (
+                Body::dynamic(1.0).charge(Real::INFINITY),
+                BodyError::NonFinite("charge"),
+            ),
+            (Body::fixed().density(1.0), BodyError::InvalidVolume(0.0)),
+            (
+                Body::dynamic(1.0).point_volume(-1.0),
+                BodyError::InvalidVolume(-1.0),
+            ),
+            (
+                Body::dynamic(1.0).cross_sectional_area(-0.5),
+                BodyError::InvalidArea(-0.5),
+            ),
+            (
+                Body::fixed().shape(Shape::sphere(1.0)).density(-1.0),
+                BodyError::InvalidDensity(-1.0),
+            ),
+        ];
+        for (builder, error) in invalid.iter() {
+            assert_eq!(builder.build().unwrap_err(), *error);
+        }
+
+        // NaN never compares equal, so check the variant instead
+        let error = Body::dynamic(1.0)
+            .shape(Shape::sphere(Real::NAN))
+            .build()
+            .unwrap_err();
+        assert!(matches!(error, BodyError::InvalidRadius(radius) if radius.is_nan()));
+    }
+
+    #[test]
+    fn builder_derives_mass_from_density() {
+        let body = Body::dynamic(1.0)
+            .shape(Shape::sphere(0.5))
+            .density(1000.0)
+            .build()
+            .unwrap();
+        let volume = 4.0 / 3.0 * std::f32::consts::PI * 0.125;
+        assert!((body.mass() - 1000.0 * volume).abs() < 1e-2);
+
+        // Static bodies and point particles are valid without a mass
+        assert!(Body::fixed().build().unwrap().has_infinite_mass());
+        assert!(Body::dynamic(1.0).shape(Shape::sphere(0.0)).build().is_ok());
     }
 }
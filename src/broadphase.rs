@@ -0,0 +1,518 @@
+use crate::{Aabb, Body, BodyHandle, BodySet, Real, Vector3};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+/// A dynamic bounding volume hierarchy over the bodies in a world.
+///
+/// Each bounded body is stored in a leaf with a fattened bounding box,
+/// so that small movements don't require the tree to be restructured.
+/// Unbounded bodies such as planes can't be placed in the tree and
+/// are reported as candidates for every query.
+pub struct Broadphase {
+    /// How far leaf boxes are grown beyond the bodies they contain
+    pub margin: Real,
+
+    nodes: Vec<Node>,
+    free_nodes: Vec<usize>,
+    root: Option<usize>,
+    leaves: HashMap<BodyHandle, usize>,
+    unbounded: HashSet<BodyHandle>,
+}
+
+#[derive(Copy, Clone)]
+enum NodeKind {
+    Leaf(BodyHandle),
+    Branch(usize, usize),
+}
+
+#[derive(Copy, Clone)]
+struct Node {
+    aabb: Aabb,
+    parent: Option<usize>,
+    kind: NodeKind,
+}
+
+impl Default for Broadphase {
+    fn default() -> Self {
+        Self::new(0.1)
+    }
+}
+
+impl Broadphase {
+    pub fn new(margin: Real) -> Self {
+        Self {
+            margin,
+            nodes: Vec::new(),
+            free_nodes: Vec::new(),
+            root: None,
+            leaves: HashMap::new(),
+            unbounded: HashSet::new(),
+        }
+    }
+
+    /// Brings the tree up to date with the current body positions,
+    /// adding new bodies and dropping removed ones.
+    pub fn update(&mut self, bodies: &BodySet) {
+        for (body_handle, body) in bodies.iter() {
+            self.update_body(body_handle, body);
+        }
+
+        let stale_leaves = self
+            .leaves
+            .keys()
+            .filter(|body_handle| !bodies.contains(**body_handle))
+            .copied()
+            .collect::<Vec<_>>();
+        for body_handle in stale_leaves {
+            self.remove(body_handle);
+        }
+        self.unbounded
+            .retain(|body_handle| bodies.contains(*body_handle));
+    }
+
+    /// Adds a body to the tree, or moves it if it has left its leaf's bounds.
+    pub fn update_body(&mut self, body_handle: BodyHandle, body: &Body) {
+        match body.shape.aabb(body.position) {
+            Some(aabb) => {
+                self.unbounded.remove(&body_handle);
+                if let Some(&leaf) = self.leaves.get(&body_handle) {
+                    if self.nodes[leaf].aabb.contains(&aabb) {
+                        return;
+                    }
+                    self.remove_leaf(leaf);
+                }
+                let leaf = self.insert_leaf(body_handle, aabb.expand(self.margin));
+                self.leaves.insert(body_handle, leaf);
+            }
+            None => {
+                if let Some(leaf) = self.leaves.remove(&body_handle) {
+                    self.remove_leaf(leaf);
+                }
+                self.unbounded.insert(body_handle);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, body_handle: BodyHandle) {
+        if let Some(leaf) = self.leaves.remove(&body_handle) {
+            self.remove_leaf(leaf);
+        }
+        self.unbounded.remove(&body_handle);
+    }
+
+    /// Reports every body whose bounds may overlap the given box.
+    pub fn query_aabb(&self, aabb: &Aabb, mut callback: impl FnMut(BodyHandle)) {
+        self.unbounded.iter().copied().for_each(&mut callback);
+        self.traverse(|node_aabb| node_aabb.intersects(aabb), callback);
+    }
+
+    /// Reports every body whose bounds may be hit by a sphere of the given
+    /// radius swept along a normalized direction.
+    pub fn query_ray(
+        &self,
+        origin: Vector3,
+        direction: Vector3,
+        radius: Real,
+        max_distance: Real,
+        mut callback: impl FnMut(BodyHandle),
+    ) {
+        self.unbounded.iter().copied().for_each(&mut callback);
+        self.traverse(
+            |node_aabb| {
+                node_aabb
+                    .expand(radius)
+                    .cast_ray(origin, direction, max_distance)
+                    .is_some()
+            },
+            callback,
+        );
+    }
+
+    /// Visits bodies in order of increasing distance from a point.
+    ///
+    /// The callback returns the exact distance to a body, or `None` to skip it,
+    /// and the search stops once `count` bodies have been accepted and no
+    /// closer body can remain. Returns the accepted bodies, nearest first.
+    pub fn query_nearest(
+        &self,
+        point: Vector3,
+        count: usize,
+        mut distance: impl FnMut(BodyHandle) -> Option<Real>,
+    ) -> Vec<BodyHandle> {
+        let mut nearest: BinaryHeap<Candidate<BodyHandle>> = BinaryHeap::new();
+        let mut accept =
+            |body_handle: BodyHandle, nearest: &mut BinaryHeap<Candidate<BodyHandle>>| {
+                if let Some(distance) = distance(body_handle) {
+                    nearest.push(Candidate(distance, body_handle));
+                    if nearest.len() > count {
+                        nearest.pop();
+                    }
+                }
+            };
+
+        if count == 0 {
+            return Vec::new();
+        }
+
+        for body_handle in self.unbounded.iter() {
+            accept(*body_handle, &mut nearest);
+        }
+
+        // Best-first search through the tree, keyed on the distance to each node's bounds
+        let mut pending = BinaryHeap::new();
+        if let Some(root) = self.root {
+            let distance = self.nodes[root]
+                .aabb
+                .distance_squared_to_point(point)
+                .sqrt();
+            pending.push(Reverse(Candidate(distance, root)));
+        }
+
+        while let Some(Reverse(Candidate(node_distance, index))) = pending.pop() {
+            let furthest = nearest.peek().map(|candidate| candidate.0);
+            if nearest.len() == count && furthest.is_some_and(|furthest| node_distance > furthest) {
+                break;
+            }
+
+            match self.nodes[index].kind {
+                NodeKind::Leaf(body_handle) => accept(body_handle, &mut nearest),
+                NodeKind::Branch(left, right) => {
+                    for child in [left, right].iter().copied() {
+                        let distance = self.nodes[child]
+                            .aabb
+                            .distance_squared_to_point(point)
+                            .sqrt();
+                        pending.push(Reverse(Candidate(distance, child)));
+                    }
+                }
+            }
+        }
+
+        let mut nearest = nearest.into_vec();
+        nearest.sort();
+        nearest
+            .into_iter()
+            .map(|Candidate(_, body_handle)| body_handle)
+            .collect()
+    }
+
+    fn traverse(&self, mut visit: impl FnMut(&Aabb) -> bool, mut callback: impl FnMut(BodyHandle)) {
+        let mut stack = self.root.into_iter().collect::<Vec<_>>();
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !visit(&node.aabb) {
+                continue;
+            }
+            match node.kind {
+                NodeKind::Leaf(body_handle) => callback(body_handle),
+                NodeKind::Branch(left, right) => {
+                    stack.push(left);
+                    stack.push(right);
+                }
+            }
+        }
+    }
+
+    fn allocate(&mut self, node: Node) -> usize {
+        match self.free_nodes.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    fn insert_leaf(&mut self, body_handle: BodyHandle, aabb: Aabb) -> usize {
+        let leaf = self.allocate(Node {
+            aabb,
+            parent: None,
+            kind: NodeKind::Leaf(body_handle),
+        });
+
+        let mut sibling = match self.root {
+            Some(root) => root,
+            None => {
+                self.root = Some(leaf);
+                return leaf;
+            }
+        };
+
+        // Descend towards the sibling that grows the tree's surface area the least
+        while let NodeKind::Branch(left, right) = self.nodes[sibling].kind {
+            let area = self.nodes[sibling].aabb.surface_area();
+            let combined_area = self.nodes[sibling].aabb.merge(&aabb).surface_area();
+
+            // Cost of pairing the new leaf with this node directly
+            let cost = 2.0 * combined_area;
+            let inheritance_cost = 2.0 * (combined_area - area);
+
+            let descend_cost = |child: usize| {
+                let child_aabb = &self.nodes[child].aabb;
+                let merged_area = child_aabb.merge(&aabb).surface_area();
+                match self.nodes[child].kind {
+                    NodeKind::Leaf(_) => merged_area + inheritance_cost,
+                    NodeKind::Branch(..) => {
+                        merged_area - child_aabb.surface_area() + inheritance_cost
+                    }
+                }
+            };
+            let left_cost = descend_cost(left);
+            let right_cost = descend_cost(right);
+
+            if cost < left_cost && cost < right_cost {
+                break;
+            }
+            sibling = if left_cost < right_cost { left } else { right };
+        }
+
+        let old_parent = self.nodes[sibling].parent;
+        let new_parent = self.allocate(Node {
+            aabb: self.nodes[sibling].aabb.merge(&aabb),
+            parent: old_parent,
+            kind: NodeKind::Branch(sibling, leaf),
+        });
+        self.nodes[sibling].parent = Some(new_parent);
+        self.nodes[leaf].parent = Some(new_parent);
+
+        match old_parent {
+            Some(old_parent) => {
+                self.replace_child(old_parent, sibling, new_parent);
+                self.refit(old_parent);
+            }
+            None => self.root = Some(new_parent),
+        }
+
+        leaf
+    }
+
+    fn remove_leaf(&mut self, leaf: usize) {
+        self.free_nodes.push(leaf);
+
+        let parent = match self.nodes[leaf].parent {
+            Some(parent) => parent,
+            None => {
+                self.root = None;
+                return;
+            }
+        };
+
+        let sibling = match self.nodes[parent].kind {
+            NodeKind::Branch(left, right) if left == leaf => right,
+            NodeKind::Branch(left, _) => left,
+            NodeKind::Leaf(_) => unreachable!("Leaf nodes can't be parents"),
+        };
+
+        // The parent is no longer needed, so the sibling takes its place
+        let grandparent = self.nodes[parent].parent;
+        self.free_nodes.push(parent);
+        self.nodes[sibling].parent = grandparent;
+        match grandparent {
+            Some(grandparent) => {
+                self.replace_child(grandparent, parent, sibling);
+                self.refit(grandparent);
+            }
+            None => self.root = Some(sibling),
+        }
+    }
+
+    fn replace_child(&mut self, parent: usize, old_child: usize, new_child: usize) {
+        if let NodeKind::Branch(left, right) = &mut self.nodes[parent].kind {
+            if *left == old_child {
+                *left = new_child;
+            } else {
+                *right = new_child;
+            }
+        }
+    }
+
+    /// Recomputes the bounds of a node and all of its ancestors.
+    fn refit(&mut self, index: usize) {
+        let mut current = Some(index);
+        while let Some(index) = current {
+            if let NodeKind::Branch(left, right) = self.nodes[index].kind {
+                self.nodes[index].aabb = self.nodes[left].aabb.merge(&self.nodes[right].aabb);
+            }
+            current = self.nodes[index].parent;
+        }
+    }
+}
+
+/// Orders items by a distance so they can be kept in a heap.
+struct Candidate<T>(Real, T);
+
+impl<T> PartialEq for Candidate<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Candidate<T> {}
+
+impl<T> PartialOrd for Candidate<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Candidate<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Shape;
+    use std::collections::BTreeSet;
+
+    /// Scatters spheres of varying sizes through a box without any randomness.
+    fn scattered_body(i: usize) -> Body {
+        let position = Vector3::new(
+            ((i * 7) % 23) as Real,
+            ((i * 11) % 17) as Real,
+            ((i * 5) % 19) as Real,
+        );
+        Body::dynamic(1.0)
+            .position(position)
+            .shape(Shape::sphere(0.25 + (i % 4) as Real * 0.5))
+            .build()
+            .unwrap()
+    }
+
+    fn bounds(body: &Body) -> Aabb {
+        body.shape.aabb(body.position).unwrap()
+    }
+
+    fn assert_matches_brute_force(broadphase: &Broadphase, bodies: &BodySet) {
+        let queries = [
+            Aabb::new(Vector3::new(2.0, 3.0, 4.0), Vector3::new(9.0, 8.0, 12.0)),
+            Aabb::new(
+                Vector3::new(-5.0, -5.0, -5.0),
+                Vector3::new(30.0, 30.0, 30.0),
+            ),
+            Aabb::from_sphere(Vector3::new(15.0, 2.0, 7.0), 1.5),
+        ];
+        for query in queries.iter() {
+            let mut found = Vec::new();
+            broadphase.query_aabb(query, |body_handle| found.push(body_handle));
+            let found_set = found.iter().copied().collect::<BTreeSet<_>>();
+            assert_eq!(found.len(), found_set.len(), "bodies reported twice");
+
+            for (body_handle, body) in bodies.iter() {
+                if bounds(body).intersects(query) {
+                    assert!(found_set.contains(&body_handle));
+                }
+            }
+            assert!(found_set
+                .iter()
+                .all(|&body_handle| bodies.contains(body_handle)));
+        }
+
+        let rays = [
+            (
+                Vector3::new(-1.0, 4.0, 6.0),
+                Vector3::new(1.0, 0.0, 0.0),
+                0.0,
+            ),
+            (
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(1.0, 1.0, 1.0).normalize(),
+                0.5,
+            ),
+            (
+                Vector3::new(10.0, 20.0, 9.0),
+                Vector3::new(0.0, -1.0, 0.0),
+                1.0,
+            ),
+        ];
+        for &(origin, direction, radius) in rays.iter() {
+            let mut found = BTreeSet::new();
+            broadphase.query_ray(origin, direction, radius, 40.0, |body_handle| {
+                found.insert(body_handle);
+            });
+
+            for (body_handle, body) in bodies.iter() {
+                let hit = bounds(body)
+                    .expand(radius)
+                    .cast_ray(origin, direction, 40.0)
+                    .is_some();
+                if hit {
+                    assert!(found.contains(&body_handle));
+                }
+            }
+        }
+
+        let points = [
+            Vector3::new(5.0, 5.0, 5.0),
+            Vector3::new(20.0, 0.0, 18.0),
+            Vector3::new(-10.0, 8.0, 3.0),
+        ];
+        for &point in points.iter() {
+            let distance = |body: &Body| body.shape.distance_to_point(body.position, point);
+            let nearest = broadphase.query_nearest(point, 5, |body_handle| {
+                bodies.get(body_handle).map(distance)
+            });
+
+            let mut expected = bodies
+                .iter()
+                .map(|(_, body)| distance(body))
+                .collect::<Vec<_>>();
+            expected.sort_by(|a, b| a.total_cmp(b));
+            expected.truncate(5);
+
+            let actual = nearest
+                .iter()
+                .map(|&body_handle| distance(&bodies[body_handle]))
+                .collect::<Vec<_>>();
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn queries_match_brute_force_after_inserts_moves_and_removals() {
+        let mut bodies = BodySet::new();
+        let mut broadphase = Broadphase::default();
+
+        let body_handles = (0..60)
+            .map(|i| bodies.insert(scattered_body(i)))
+            .collect::<Vec<_>>();
+        broadphase.update(&bodies);
+        assert_matches_brute_force(&broadphase, &bodies);
+
+        // Small moves stay inside the fattened leaves, large ones restructure the tree
+        for (i, &body_handle) in body_handles.iter().enumerate() {
+            let offset = if i % 3 == 0 {
+                Vector3::new(0.05, -0.05, 0.05)
+            } else {
+                Vector3::new((i % 5) as Real * 2.0, -3.0, (i % 7) as Real)
+            };
+            bodies[body_handle].position += offset;
+        }
+        broadphase.update(&bodies);
+        assert_matches_brute_force(&broadphase, &bodies);
+
+        for &body_handle in body_handles.iter().step_by(2) {
+            bodies.remove(body_handle);
+            broadphase.remove(body_handle);
+        }
+        assert_matches_brute_force(&broadphase, &bodies);
+
+        // Removed bodies that the tree wasn't told about are dropped on the next update
+        for &body_handle in body_handles.iter().skip(1).step_by(4) {
+            bodies.remove(body_handle);
+        }
+        for i in 60..80 {
+            let body_handle = bodies.insert(scattered_body(i));
+            broadphase.update_body(body_handle, &bodies[body_handle]);
+        }
+        broadphase.update(&bodies);
+        assert_matches_brute_force(&broadphase, &bodies);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::{Body, BodyHandle};
+
+/// A user supplied predicate deciding whether two bodies may interact.
+/// It is consulted after the bodies' collision filters have accepted the pair.
+pub type PairFilter = Box<dyn Fn(BodyHandle, &Body, BodyHandle, &Body) -> bool>;
+
+/// Decides which bodies are allowed to interact with each other.
+///
+/// Two bodies interact when each one's memberships intersect the other's filter.
+/// Collision groups take priority over layers: bodies sharing a positive group
+/// always interact and bodies sharing a negative group never do.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CollisionFilter {
+    /// The layers this body belongs to
+    pub memberships: u32,
+
+    /// The layers this body is allowed to interact with
+    pub filter: u32,
+
+    /// The collision group of the body, or zero for no group
+    pub group: i32,
+}
+
+impl Default for CollisionFilter {
+    fn default() -> Self {
+        Self::new(Self::ALL, Self::ALL)
+    }
+}
+
+impl CollisionFilter {
+    /// Every layer
+    pub const ALL: u32 = u32::MAX;
+
+    /// No layers
+    pub const NONE: u32 = 0;
+
+    pub fn new(memberships: u32, filter: u32) -> Self {
+        Self {
+            memberships,
+            filter,
+            group: 0,
+        }
+    }
+
+    pub fn with_group(mut self, group: i32) -> Self {
+        self.group = group;
+        self
+    }
+
+    /// Checks whether this body may interact with a body using the other filter.
+    pub fn can_interact(&self, other: &Self) -> bool {
+        if self.group != 0 && self.group == other.group {
+            return self.group > 0;
+        }
+
+        (self.memberships & other.filter) != 0 && (other.memberships & self.filter) != 0
+    }
+}
@@ -1,15 +1,31 @@
-use crate::{BodySet, Handle, Real, Vector3};
+use crate::{BodyHandle, BodySet, Real, Vector3};
 
+/// Generates contacts between bodies, such as the
+/// constraints imposed by cables and rods.
 pub trait ContactGenerator {
-    fn add_contact(contact: &Contact, limit: u32);
+    /// Adds any contacts this generator produces to the given list,
+    /// writing no more than `limit` contacts.
+    /// Returns the number of contacts that were added.
+    fn add_contact(&self, bodies: &BodySet, contacts: &mut Vec<Contact>, limit: u32) -> u32;
+
+    /// Whether the generator depends on the given body, in which case
+    /// it is removed from the world along with the body.
+    fn references_body(&self, _body_handle: BodyHandle) -> bool {
+        false
+    }
 }
 
 /// The contact resolution routine for contacts. One
 /// resolver instance can be shared for the whole simulation.
 #[derive(Default)]
 pub struct ContactResolver {
+    /// The number of iterations allowed per resolution.
+    /// Zero means twice the number of contacts being resolved.
     pub iterations: u32,
     pub iterations_used: u32,
+
+    /// The total impulse applied to each contact during the last resolution
+    pub impulses: Vec<Real>,
 }
 
 impl ContactResolver {
@@ -20,81 +36,149 @@ impl ContactResolver {
     /// passed to separate calls to resolveContacts, as the
     /// resolution algorithm takes much longer for lots of contacts
     /// than it does for the same number of contacts in small sets.
-    pub fn resolve_contacts(&mut self, contacts: &[Contact], duration: Real, bodies: &mut BodySet) {
-        while self.iterations_used < self.iterations {
+    pub fn resolve_contacts(
+        &mut self,
+        contacts: &mut [Contact],
+        duration: Real,
+        bodies: &mut BodySet,
+    ) {
+        let iterations = match self.iterations {
+            0 => contacts.len() as u32 * 2,
+            iterations => iterations,
+        };
+
+        self.iterations_used = 0;
+        self.impulses.clear();
+        self.impulses.resize(contacts.len(), 0.0);
+        while self.iterations_used < iterations {
             // Find the contact with the largest closing velocity
-            let (max_index, max_separating_velocity) = contacts
+            // that still needs resolving
+            let mut max_index = None;
+            let mut max_closing_velocity = 0.0;
+            for (index, contact) in contacts.iter().enumerate() {
+                let closing_velocity = -contact.separating_velocity(bodies);
+                let needs_resolution = closing_velocity > 0.0 || contact.penetration > 0.0;
+
+                // Resolving can't move a pair of immovable bodies, so picking one
+                // would only use up the iterations on every pass
+                if needs_resolution
+                    && contact.total_inverse_mass(bodies) > 0.0
+                    && (max_index.is_none() || closing_velocity > max_closing_velocity)
+                {
+                    max_index = Some(index);
+                    max_closing_velocity = closing_velocity;
+                }
+            }
+
+            // Every contact is separating and free of interpenetration
+            let max_index = match max_index {
+                Some(max_index) => max_index,
+                None => break,
+            };
+
+            let resolution = contacts[max_index].resolve(bodies, duration);
+            self.impulses[max_index] += resolution.impulse;
+
+            // Resolving interpenetration moves the bodies, which changes
+            // the penetration of every other contact involving them
+            let (body_handle, other_body_handle) = {
+                let contact = &contacts[max_index];
+                (contact.body_handle, contact.other_body_handle)
+            };
+            for contact in contacts.iter_mut() {
+                for (moved_handle, movement) in [
+                    (body_handle, resolution.body_movement),
+                    (other_body_handle, resolution.other_body_movement),
+                ]
                 .iter()
-                .map(|contact| contact.separating_velocity(bodies))
-                .enumerate()
-                .fold((0, 0.0), |max, (index, velocity)| {
-                    if velocity > max.1 {
-                        (index, velocity)
-                    } else {
-                        max
+                {
+                    if contact.body_handle == *moved_handle {
+                        contact.penetration -= movement.dot(contact.normal);
+                    } else if contact.other_body_handle == *moved_handle {
+                        contact.penetration += movement.dot(contact.normal);
                     }
-                });
-
-            // Every contact is either separating or stationary
-            if max_separating_velocity <= 0.0 {
-                break;
+                }
             }
 
-            contacts[max_index].resolve(bodies, duration);
-
             self.iterations_used += 1;
         }
     }
 }
 
+/// The outcome of resolving a single contact.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ContactResolution {
+    /// The magnitude of the impulse applied along the contact normal
+    pub impulse: Real,
+
+    /// How far the body was moved to remove interpenetration
+    pub body_movement: Vector3,
+
+    /// How far the other body was moved to remove interpenetration
+    pub other_body_movement: Vector3,
+}
+
 /// A contact represents two objects in contact
 /// Resolving a contact removes their interpenetration, and applies sufficient
 /// impulse to keep them apart. Colliding bodies may also rebound.
+#[derive(Debug, Copy, Clone)]
 pub struct Contact {
-    pub body_handle: Handle,
+    pub body_handle: BodyHandle,
 
     /// A body with infinite mass and a velocity of zero implies a contact with scenery
-    pub other_body_handle: Handle,
+    pub other_body_handle: BodyHandle,
 
     /// The normal restitution coefficient at the contact
     pub restitution: Real,
 
-    /// The direction of the contact in world coordinates
+    /// The point of contact in world coordinates
+    pub point: Vector3,
+
+    /// The direction of the contact in world coordinates,
+    /// pointing from the other body towards the body
     pub normal: Vector3,
 
-    // The depth of penetration at thecontact
+    // The depth of penetration at the contact
     pub penetration: Real,
 }
 
 impl Contact {
-    pub fn resolve(&self, bodies: &mut BodySet, duration: Real) {
-        self.resolve_velocity(bodies, duration);
-        self.resolve_interpenetration(bodies, duration);
+    /// Resolves the contact for both velocity and interpenetration.
+    pub fn resolve(&self, bodies: &mut BodySet, duration: Real) -> ContactResolution {
+        let impulse = self.resolve_velocity(bodies, duration);
+        let (body_movement, other_body_movement) = self.resolve_interpenetration(bodies, duration);
+        ContactResolution {
+            impulse,
+            body_movement,
+            other_body_movement,
+        }
     }
 
-    fn resolve_velocity(&self, bodies: &mut BodySet, duration: Real) {
+    /// Applies the impulse needed to stop the bodies closing,
+    /// returning the magnitude of the impulse that was applied.
+    fn resolve_velocity(&self, bodies: &mut BodySet, duration: Real) -> Real {
         // Find velocity in the direction of the of the contact
         let separating_velocity = self.separating_velocity(bodies);
 
-        let impulse_required = separating_velocity > 0.0;
+        let impulse_required = separating_velocity < 0.0;
         if !impulse_required {
             // The contact is either separating or stationary
             // so there is no impulse required
-            return;
+            return 0.0;
         }
 
         let (body_acceleration, body_inverse_mass) = {
             let body = bodies
                 .get(self.body_handle)
                 .expect("Failed to lookup body!");
-            (body.acceleration, body.inverse_mass)
+            (body.acceleration, body.effective_inverse_mass())
         };
 
         let other_body_inverse_mass = {
             bodies
                 .get(self.other_body_handle)
                 .expect("Failed to lookup body!")
-                .inverse_mass
+                .effective_inverse_mass()
         };
 
         let mut new_separating_velocity = -separating_velocity * self.restitution;
@@ -118,7 +202,7 @@ impl Contact {
         // Those with lower inverse mass (higher actual mass) get less change in velocity
         let total_inverse_mass = body_inverse_mass + other_body_inverse_mass;
         if total_inverse_mass <= 0.0 {
-            return;
+            return 0.0;
         }
 
         let impulse = delta_velocity / total_inverse_mass;
@@ -130,18 +214,22 @@ impl Contact {
             let body = bodies
                 .get_mut(self.body_handle)
                 .expect("Failed to lookup body!");
-            body.velocity += impulse_per_inverse_mass * body.inverse_mass;
+            body.velocity += impulse_per_inverse_mass * body.effective_inverse_mass();
         };
 
         {
             let body = bodies
                 .get_mut(self.other_body_handle)
                 .expect("Failed to lookup body!");
-            body.velocity += impulse_per_inverse_mass * -body.inverse_mass;
+            body.velocity += impulse_per_inverse_mass * -body.effective_inverse_mass();
         };
+
+        impulse.abs()
     }
 
-    pub fn separating_velocity(&self, bodies: &mut BodySet) -> Real {
+    /// The relative velocity of the bodies along the contact normal.
+    /// This is negative when the bodies are moving towards each other.
+    pub fn separating_velocity(&self, bodies: &BodySet) -> Real {
         let body = bodies
             .get(self.body_handle)
             .expect("Failed to lookup body!");
@@ -153,44 +241,200 @@ impl Contact {
         (body.velocity - other_body.velocity).dot(self.normal)
     }
 
-    fn resolve_interpenetration(&self, bodies: &mut BodySet, _duration: Real) {
+    /// The combined inverse mass of the bodies, which is zero when neither can be moved.
+    fn total_inverse_mass(&self, bodies: &BodySet) -> Real {
+        let body = bodies
+            .get(self.body_handle)
+            .expect("Failed to lookup body!");
+
+        let other_body = bodies
+            .get(self.other_body_handle)
+            .expect("Failed to lookup body!");
+
+        body.effective_inverse_mass() + other_body.effective_inverse_mass()
+    }
+
+    fn resolve_interpenetration(
+        &self,
+        bodies: &mut BodySet,
+        _duration: Real,
+    ) -> (Vector3, Vector3) {
         // If we don't have any penetration, skip this step.
         if self.penetration <= 0.0 {
-            return;
+            return (Vector3::zero(), Vector3::zero());
         }
 
-        // Find the amount of penetration resolution per unit of inverse mass
-        let move_per_inverse_mass = {
-            // The movement of each object is based on their inverse mass, so
-            // total that.
+        let (body_inverse_mass, other_body_inverse_mass) = {
             let body = bodies
                 .get(self.body_handle)
                 .expect("Failed to lookup body!");
             let other_body = bodies
-                .get(self.body_handle)
+                .get(self.other_body_handle)
                 .expect("Failed to lookup body!");
-
-            // If all particles have infinite mass, then we do nothing
-            let total_inverse_mass = body.inverse_mass + other_body.inverse_mass;
-            if total_inverse_mass <= 0.0 {
-                return;
-            }
-            self.normal * (self.penetration / total_inverse_mass)
+            (
+                body.effective_inverse_mass(),
+                other_body.effective_inverse_mass(),
+            )
         };
 
+        // The movement of each object is based on their inverse mass, so
+        // total that. If all particles have infinite mass, then we do nothing
+        let total_inverse_mass = body_inverse_mass + other_body_inverse_mass;
+        if total_inverse_mass <= 0.0 {
+            return (Vector3::zero(), Vector3::zero());
+        }
+
+        // Find the amount of penetration resolution per unit of inverse mass
+        let move_per_inverse_mass = self.normal * (self.penetration / total_inverse_mass);
+        let body_movement = move_per_inverse_mass * body_inverse_mass;
+        let other_body_movement = move_per_inverse_mass * -other_body_inverse_mass;
+
         // Apply the penetration resolution
-        {
-            let body = bodies
-                .get_mut(self.body_handle)
-                .expect("Failed to lookup body!");
-            body.position += move_per_inverse_mass * body.inverse_mass;
-        };
+        bodies
+            .get_mut(self.body_handle)
+            .expect("Failed to lookup body!")
+            .position += body_movement;
 
-        {
-            let body = bodies
-                .get_mut(self.other_body_handle)
-                .expect("Failed to lookup body!");
-            body.position += move_per_inverse_mass * -body.inverse_mass;
+        bodies
+            .get_mut(self.other_body_handle)
+            .expect("Failed to lookup body!")
+            .position += other_body_movement;
+
+        (body_movement, other_body_movement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Body, PhysicsWorld};
+
+    /// Keeps two spheres of the given radius from overlapping.
+    struct SphereContact {
+        body_handle: BodyHandle,
+        other_body_handle: BodyHandle,
+        radius: Real,
+    }
+
+    impl ContactGenerator for SphereContact {
+        fn add_contact(&self, bodies: &BodySet, contacts: &mut Vec<Contact>, limit: u32) -> u32 {
+            let position = bodies[self.body_handle].position;
+            let other_position = bodies[self.other_body_handle].position;
+            let offset = position - other_position;
+            let penetration = 2.0 * self.radius - offset.magnitude();
+            if limit == 0 || penetration <= 0.0 {
+                return 0;
+            }
+
+            contacts.push(Contact {
+                body_handle: self.body_handle,
+                other_body_handle: self.other_body_handle,
+                restitution: 0.0,
+                point: other_position + offset * 0.5,
+                normal: offset.normalize(),
+                penetration,
+            });
+            1
+        }
+    }
+
+    fn colliding_world(other_body: Body) -> (PhysicsWorld, BodyHandle, BodyHandle) {
+        let mut world = PhysicsWorld::default();
+        let body_handle = world.add_body(
+            Body::dynamic(1.0)
+                .damping(1.0)
+                .position(Vector3::new(1.5, 0.0, 0.0))
+                .build()
+                .unwrap(),
+        );
+        let other_body_handle = world.add_body(other_body);
+        world.add_contact_generator(SphereContact {
+            body_handle,
+            other_body_handle,
+            radius: 0.5,
+        });
+        (world, body_handle, other_body_handle)
+    }
+
+    #[test]
+    fn kinematic_body_pushes_dynamic_body_without_being_pushed_back() {
+        let velocity = Vector3::new(2.0, 0.0, 0.0);
+        let (mut world, body_handle, kinematic_handle) =
+            colliding_world(Body::kinematic().velocity(velocity).build().unwrap());
+
+        let duration = 1.0 / 60.0;
+        for tick in 1..=120 {
+            world.tick(duration);
+
+            let kinematic = &world.bodies[kinematic_handle];
+            assert_eq!(kinematic.velocity, velocity);
+            let expected = velocity * (tick as Real * duration);
+            assert!((kinematic.position - expected).magnitude() < 1e-3);
+
+            // The dynamic body is kept ahead of the kinematic body
+            let body = &world.bodies[body_handle];
+            assert!(body.position.x - kinematic.position.x >= 1.0 - 1e-4);
+        }
+
+        // Once caught, the dynamic body is carried along at the kinematic body's speed
+        assert!(world.bodies[body_handle].position.x > 1.5 + 1.0);
+        assert!((world.bodies[body_handle].velocity - velocity).magnitude() < 1e-3);
+    }
+
+    #[test]
+    fn static_body_stops_dynamic_body() {
+        let (mut world, body_handle, static_handle) =
+            colliding_world(Body::fixed().position(Vector3::zero()).build().unwrap());
+        world.bodies[body_handle].velocity = Vector3::new(-3.0, 0.0, 0.0);
+
+        for _ in 0..60 {
+            world.tick(1.0 / 60.0);
+            assert_eq!(world.bodies[static_handle].position, Vector3::zero());
+            assert!(world.bodies[body_handle].position.x >= 1.0 - 1e-4);
+        }
+        assert!(world.bodies[body_handle].velocity.magnitude() < 1e-4);
+    }
+
+    #[test]
+    fn immovable_pairs_do_not_use_up_the_iterations() {
+        let mut bodies = BodySet::new();
+        let fixed = bodies.insert(Body::fixed().build().unwrap());
+        let kinematic = bodies.insert(
+            Body::kinematic()
+                .position(Vector3::new(0.5, 0.0, 0.0))
+                .velocity(Vector3::new(-10.0, 0.0, 0.0))
+                .build()
+                .unwrap(),
+        );
+        let body = bodies.insert(
+            Body::dynamic(1.0)
+                .position(Vector3::new(0.0, 0.9, 0.0))
+                .velocity(Vector3::new(0.0, -1.0, 0.0))
+                .build()
+                .unwrap(),
+        );
+
+        // The stuck pair closes faster than the real contact, so it would be picked first
+        let contact = |body_handle, normal, penetration| Contact {
+            body_handle,
+            other_body_handle: fixed,
+            restitution: 0.0,
+            point: Vector3::zero(),
+            normal,
+            penetration,
         };
+        let mut contacts = [
+            contact(kinematic, Vector3::x(), 0.5),
+            contact(body, Vector3::y(), 0.1),
+        ];
+
+        let mut resolver = ContactResolver::default();
+        resolver.resolve_contacts(&mut contacts, 1.0 / 60.0, &mut bodies);
+
+        assert_eq!(bodies[kinematic].position, Vector3::new(0.5, 0.0, 0.0));
+        assert!((bodies[body].position.y - 1.0).abs() < 1e-5);
+        assert!(bodies[body].velocity.magnitude() < 1e-5);
+        assert_eq!(resolver.impulses[0], 0.0);
+        assert!(resolver.iterations_used < 4);
     }
 }
@@ -0,0 +1,255 @@
+use crate::{BodyHandle, BodySet, ForceContext, ForceGenerator, Real, Vector3};
+use std::{any::Any, collections::BTreeMap};
+
+/// Pushes bodies along a direction, like a rocket engine or a propeller.
+///
+/// The thrust acts through the body's center and turns with the body.
+pub struct Thruster {
+    /// The direction of the thrust, in body space
+    pub direction: Vector3,
+
+    /// The force produced at full throttle
+    pub max_thrust: Real,
+
+    throttle: Real,
+}
+
+impl Thruster {
+    pub fn new(direction: Vector3, max_thrust: Real) -> Self {
+        Self {
+            direction,
+            max_thrust,
+            throttle: 0.0,
+        }
+    }
+
+    pub fn throttle(&self) -> Real {
+        self.throttle
+    }
+
+    /// Sets the fraction of the maximum thrust produced, clamped to the range 0 to 1.
+    pub fn set_throttle(&mut self, throttle: Real) {
+        self.throttle = throttle.clamp(0.0, 1.0);
+    }
+
+    /// The force currently produced by the thruster, in body space.
+    pub fn thrust(&self) -> Vector3 {
+        self.direction.normalize() * (self.max_thrust * self.throttle)
+    }
+}
+
+impl ForceGenerator for Thruster {
+    fn apply(&mut self, _context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        if let Some(body) = bodies.get_mut(body_handle) {
+            let thrust = body.direction_to_world(self.thrust());
+            body.add_force(&thrust);
+        }
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        Some(self)
+    }
+}
+
+/// Where a `PidController` drives bodies.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PidTarget {
+    /// Drives bodies towards a position along every axis.
+    Position(Vector3),
+
+    /// Drives bodies towards a height along the y axis, leaving the other axes free.
+    Height(Real),
+}
+
+impl PidTarget {
+    /// How far a body at the given position is from the target.
+    fn error(&self, position: Vector3) -> Vector3 {
+        match *self {
+            Self::Position(target) => target - position,
+            Self::Height(height) => Vector3::new(0.0, height - position.y, 0.0),
+        }
+    }
+
+    /// Restricts a vector to the axes being controlled.
+    fn mask(&self, vector: Vector3) -> Vector3 {
+        match *self {
+            Self::Position(_) => vector,
+            Self::Height(_) => Vector3::new(0.0, vector.y, 0.0),
+        }
+    }
+}
+
+/// The accumulated state of a `PidController` for one body.
+#[derive(Debug, Default, Copy, Clone)]
+struct PidState {
+    integral: Vector3,
+}
+
+/// Drives bodies towards a target with a proportional-integral-derivative controller.
+///
+/// The proportional term pulls bodies towards the target, the derivative term damps their
+/// approach and the integral term builds up to cancel steady forces such as gravity.
+/// The derivative is taken of the body's position rather than the error, so moving the
+/// target doesn't produce a sudden kick.
+///
+/// To prevent windup, the integral is clamped and stops accumulating while the output
+/// is saturated at the maximum force. Each body keeps its own integral.
+pub struct PidController {
+    pub target: PidTarget,
+
+    /// The force per meter of error
+    pub proportional_gain: Real,
+
+    /// The force per meter second of accumulated error
+    pub integral_gain: Real,
+
+    /// The force per meter per second of speed
+    pub derivative_gain: Real,
+
+    /// The largest force the controller applies
+    pub max_force: Real,
+
+    /// The largest accumulated error, in meter seconds
+    pub max_integral: Real,
+
+    states: BTreeMap<BodyHandle, PidState>,
+}
+
+impl PidController {
+    pub fn new(
+        target: PidTarget,
+        proportional_gain: Real,
+        integral_gain: Real,
+        derivative_gain: Real,
+    ) -> Self {
+        Self {
+            target,
+            proportional_gain,
+            integral_gain,
+            derivative_gain,
+            max_force: Real::INFINITY,
+            max_integral: Real::INFINITY,
+            states: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_max_force(mut self, max_force: Real) -> Self {
+        self.max_force = max_force;
+        self
+    }
+
+    pub fn with_max_integral(mut self, max_integral: Real) -> Self {
+        self.max_integral = max_integral;
+        self
+    }
+
+    /// Forgets the accumulated error of every body.
+    pub fn reset(&mut self) {
+        self.states.clear();
+    }
+}
+
+impl ForceGenerator for PidController {
+    fn apply(&mut self, context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        let body = match bodies.get_mut(body_handle) {
+            Some(body) => body,
+            None => return,
+        };
+
+        let state = self.states.entry(body_handle).or_default();
+        let error = self.target.error(body.position);
+        let velocity = self.target.mask(body.velocity);
+
+        let integral =
+            (state.integral + error * context.duration).clamp_magnitude(self.max_integral);
+        let output = error * self.proportional_gain + integral * self.integral_gain
+            - velocity * self.derivative_gain;
+
+        // Only accumulate error while the output can still act on it
+        let saturated = output.magnitude() > self.max_force && output.dot(error) > 0.0;
+        if !saturated {
+            state.integral = integral;
+        }
+
+        let force = output.clamp_magnitude(self.max_force);
+        body.add_force(&force);
+    }
+
+    fn update(
+        &mut self,
+        _context: &ForceContext,
+        _bodies: &BodySet,
+        registered_bodies: &[BodyHandle],
+    ) {
+        // Forget bodies that are no longer controlled
+        self.states
+            .retain(|body_handle, _| registered_bodies.contains(body_handle));
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Body, ForceGeneratorHandle, Gravity, PhysicsWorld};
+
+    fn hover_world(controller: PidController) -> (PhysicsWorld, BodyHandle, ForceGeneratorHandle) {
+        let mut world = PhysicsWorld::default();
+        let body = world.add_body(Body::dynamic(1.0).damping(1.0).build().unwrap());
+        let gravity = world.add_force_generator(Gravity::default());
+        let controller = world.add_force_generator(controller);
+        world.register_force(gravity, body);
+        world.register_force(controller, body);
+        (world, body, controller)
+    }
+
+    #[test]
+    fn pid_hover_reaches_its_target() {
+        let (mut world, body, _) =
+            hover_world(PidController::new(PidTarget::Height(10.0), 20.0, 10.0, 8.0));
+
+        for _ in 0..1200 {
+            world.tick(1.0 / 60.0);
+        }
+
+        // The integral term has to cancel gravity for the body to settle at the target
+        let body = &world.bodies[body];
+        assert!((body.position.y - 10.0).abs() < 0.05, "{:?}", body.position);
+        assert!(body.velocity.magnitude() < 0.05, "{:?}", body.velocity);
+    }
+
+    #[test]
+    fn pid_integral_does_not_wind_up_while_saturated() {
+        let (mut world, body, controller) = hover_world(
+            PidController::new(PidTarget::Height(50.0), 20.0, 10.0, 8.0).with_max_force(30.0),
+        );
+
+        let mut highest: Real = 0.0;
+        for _ in 0..3600 {
+            world.tick(1.0 / 60.0);
+            highest = highest.max(world.bodies[body].position.y);
+        }
+
+        // The long climb at full force mustn't build up an integral that overshoots the target
+        let body = &world.bodies[body];
+        assert!((body.position.y - 50.0).abs() < 0.05, "{:?}", body.position);
+        assert!(highest < 56.0, "overshot to {}", highest);
+
+        let controller = world.force_generators[controller]
+            .downcast_ref::<PidController>()
+            .unwrap();
+        let integral = controller.states.values().next().unwrap().integral;
+        assert!(integral.magnitude() < 1.5, "{:?}", integral);
+    }
+}
@@ -0,0 +1,52 @@
+use crate::{BodyHandle, Real, SensorHandle, Vector3};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ContactEventKind {
+    /// The bodies came into contact during this tick
+    Started,
+
+    /// The bodies were already in contact and remain so
+    Persisted,
+
+    /// The bodies were in contact during the previous tick but no longer are
+    Ended,
+}
+
+/// Describes a change in the contact state between two bodies.
+///
+/// When several contacts are generated between the same pair of bodies
+/// in a single tick, they are reported as one event with their impulses summed.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ContactEvent {
+    pub kind: ContactEventKind,
+    pub body_handle: BodyHandle,
+    pub other_body_handle: BodyHandle,
+
+    /// The point of contact in world coordinates.
+    /// Ended events report the last known point.
+    pub point: Vector3,
+
+    /// The contact normal, pointing from the other body towards the body
+    pub normal: Vector3,
+
+    /// The magnitude of the impulse applied to separate the bodies.
+    /// This is always zero for ended events.
+    pub impulse: Real,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SensorEventKind {
+    /// The body moved into the sensor during this tick
+    Entered,
+
+    /// The body left the sensor during this tick, or was removed from the world
+    Exited,
+}
+
+/// Describes a body entering or leaving a sensor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SensorEvent {
+    pub kind: SensorEventKind,
+    pub sensor_handle: SensorHandle,
+    pub body_handle: BodyHandle,
+}
@@ -0,0 +1,312 @@
+use crate::{
+    Body, BodyHandle, BodySet, CollisionFilter, ForceContext, ForceGenerator, Real, Vector3, Volume,
+};
+
+/// How a force field weakens towards the boundary of its volume.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum Falloff {
+    /// Full strength everywhere inside the volume
+    #[default]
+    None,
+
+    /// Grows linearly from nothing at the boundary to full strength at the given depth
+    Linear { width: Real },
+
+    /// Like `Linear`, but eases in and out of full strength
+    Smooth { width: Real },
+}
+
+impl Falloff {
+    /// The fraction of full strength at the given depth inside the volume.
+    pub fn scale(&self, depth: Real) -> Real {
+        if depth < 0.0 {
+            return 0.0;
+        }
+
+        match *self {
+            Self::None => 1.0,
+            Self::Linear { width } => (depth / width).min(1.0),
+            Self::Smooth { width } => {
+                let t = (depth / width).min(1.0);
+                t * t * (3.0 - 2.0 * t)
+            }
+        }
+    }
+}
+
+/// Restricts a force generator to the bodies inside a volume.
+///
+/// A body is inside the field when its position is inside the volume.
+/// Added to a world with `PhysicsWorld::add_force_field`, the field applies to
+/// every body inside it without being registered, though it can also be added
+/// and registered like any other force generator.
+///
+/// With falloff, the force and torque the inner generator applies to each body are scaled
+/// by the field's strength at the body's position. Forces the inner generator applies
+/// to other bodies, such as the other end of a spring, aren't scaled.
+pub struct ForceField {
+    pub volume: Volume,
+    pub falloff: Falloff,
+
+    /// Controls which bodies the field affects
+    pub collision_filter: CollisionFilter,
+
+    pub generator: Box<dyn ForceGenerator>,
+}
+
+impl ForceField {
+    pub fn new(volume: Volume, generator: impl ForceGenerator + 'static) -> Self {
+        Self {
+            volume,
+            falloff: Falloff::None,
+            collision_filter: CollisionFilter::default(),
+            generator: Box::new(generator),
+        }
+    }
+
+    pub fn with_falloff(mut self, falloff: Falloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    pub fn with_collision_filter(mut self, collision_filter: CollisionFilter) -> Self {
+        self.collision_filter = collision_filter;
+        self
+    }
+
+    /// The fraction of full strength at a point, which is zero outside the volume.
+    pub fn strength(&self, point: Vector3) -> Real {
+        self.falloff.scale(self.volume.depth(point))
+    }
+
+    /// Checks whether the field applies to a body.
+    pub fn affects(&self, body: &Body) -> bool {
+        self.collision_filter.can_interact(&body.collision_filter)
+            && self.volume.contains_point(body.position)
+    }
+}
+
+impl ForceGenerator for ForceField {
+    fn apply(&mut self, context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        let (strength, force_before, torque_before) = match bodies.get(body_handle) {
+            Some(body) if self.affects(body) => (
+                self.strength(body.position),
+                body.force_accumulator,
+                body.torque_accumulator,
+            ),
+            _ => return,
+        };
+
+        if strength <= 0.0 {
+            return;
+        }
+
+        self.generator.apply(context, body_handle, bodies);
+
+        if let Some(body) = bodies.get_mut(body_handle) {
+            let applied = body.force_accumulator - force_before;
+            body.force_accumulator = force_before + applied * strength;
+            let applied = body.torque_accumulator - torque_before;
+            body.torque_accumulator = torque_before + applied * strength;
+        }
+    }
+
+    fn update(
+        &mut self,
+        context: &ForceContext,
+        bodies: &BodySet,
+        registered_bodies: &[BodyHandle],
+    ) {
+        let inside = registered_bodies
+            .iter()
+            .copied()
+            .filter(|&body_handle| {
+                bodies
+                    .get(body_handle)
+                    .is_some_and(|body| self.affects(body))
+            })
+            .collect::<Vec<_>>();
+        self.generator.update(context, bodies, &inside);
+    }
+
+    fn references_body(&self, body_handle: BodyHandle) -> bool {
+        self.generator.references_body(body_handle)
+    }
+}
+
+/// Pushes every body with the same force.
+pub struct Directional {
+    pub force: Vector3,
+}
+
+impl ForceGenerator for Directional {
+    fn apply(&mut self, _context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        if let Some(body) = bodies.get_mut(body_handle) {
+            body.add_force(&self.force);
+        }
+    }
+}
+
+/// Pushes bodies directly away from a point, or pulls them towards it.
+pub struct Radial {
+    pub center: Vector3,
+
+    /// The force pushing bodies away from the center, or pulling them in when negative
+    pub strength: Real,
+}
+
+impl ForceGenerator for Radial {
+    fn apply(&mut self, _context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        let body = match bodies.get_mut(body_handle) {
+            Some(body) => body,
+            None => return,
+        };
+
+        // Normalizing leaves a zero offset at the center alone, so bodies there feel nothing
+        let force = (body.position - self.center).normalize() * self.strength;
+        body.add_force(&force);
+    }
+}
+
+/// Swirls bodies around an axis, like a whirlpool or a tornado.
+pub struct Vortex {
+    /// A point on the axis of the vortex
+    pub center: Vector3,
+
+    /// The axis bodies circle around, counterclockwise when viewed from its tip
+    pub axis: Vector3,
+
+    /// The force pushing bodies around the axis
+    pub strength: Real,
+
+    /// The force pulling bodies in towards the axis
+    pub pull: Real,
+
+    /// The force lifting bodies along the axis
+    pub lift: Real,
+}
+
+impl Vortex {
+    pub fn new(center: Vector3, axis: Vector3, strength: Real) -> Self {
+        Self {
+            center,
+            axis: axis.normalize(),
+            strength,
+            pull: 0.0,
+            lift: 0.0,
+        }
+    }
+
+    /// The force on a body at the given position.
+    pub fn force(&self, position: Vector3) -> Vector3 {
+        let axis = self.axis.normalize();
+        let offset = position - self.center;
+        let outward = (offset - axis * offset.dot(axis)).normalize();
+        let around = axis.cross(outward);
+        around * self.strength - outward * self.pull + axis * self.lift
+    }
+}
+
+impl ForceGenerator for Vortex {
+    fn apply(&mut self, _context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        if let Some(body) = bodies.get_mut(body_handle) {
+            let force = self.force(body.position);
+            body.add_force(&force);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PhysicsWorld;
+
+    fn context() -> ForceContext {
+        ForceContext {
+            duration: 1.0 / 60.0,
+            elapsed: 0.0,
+            tick: 0,
+            boundary: None,
+        }
+    }
+
+    fn body_at(position: Vector3) -> Body {
+        Body::dynamic(1.0)
+            .damping(1.0)
+            .position(position)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn force_field_affects_only_bodies_inside_it() {
+        let mut world = PhysicsWorld::default();
+        let lift = Vector3::new(0.0, 10.0, 0.0);
+        world.add_force_field(ForceField::new(
+            Volume::sphere(Vector3::zero(), 2.0),
+            Directional { force: lift },
+        ));
+        let inside = world.add_body(body_at(Vector3::new(1.0, 0.0, 0.0)));
+        let outside = world.add_body(body_at(Vector3::new(3.0, 0.0, 0.0)));
+
+        let duration = 1.0 / 60.0;
+        world.tick(duration);
+        assert!((world.bodies[inside].velocity - lift * duration).magnitude() < 1e-6);
+        assert_eq!(world.bodies[outside].velocity, Vector3::zero());
+    }
+
+    #[test]
+    fn falloff_scales_the_inner_force() {
+        assert_eq!(Falloff::None.scale(0.0), 1.0);
+        assert_eq!(Falloff::Linear { width: 2.0 }.scale(0.5), 0.25);
+        assert_eq!(Falloff::Smooth { width: 2.0 }.scale(1.0), 0.5);
+        assert_eq!(Falloff::Smooth { width: 2.0 }.scale(3.0), 1.0);
+        assert_eq!(Falloff::Linear { width: 2.0 }.scale(-0.1), 0.0);
+
+        // A body 0.5 inside a sphere with a falloff over the outer 2
+        let force = |falloff| {
+            let mut field = ForceField::new(
+                Volume::sphere(Vector3::zero(), 4.0),
+                Directional {
+                    force: Vector3::new(0.0, 8.0, 0.0),
+                },
+            )
+            .with_falloff(falloff);
+            let mut bodies = BodySet::new();
+            let body = bodies.insert(body_at(Vector3::new(0.0, 0.0, 3.5)));
+            field.apply(&context(), body, &mut bodies);
+            bodies[body].force_accumulator.y
+        };
+        assert_eq!(force(Falloff::None), 8.0);
+        assert_eq!(force(Falloff::Linear { width: 2.0 }), 2.0);
+        assert!((force(Falloff::Smooth { width: 2.0 }) - 8.0 * 0.156_25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn vortex_swirls_around_its_axis() {
+        let mut vortex = Vortex::new(Vector3::new(0.0, 1.0, 0.0), Vector3::y(), 3.0);
+        vortex.pull = 2.0;
+        vortex.lift = 1.0;
+
+        // Counterclockwise seen from above, pulled in and lifted up
+        let force = vortex.force(Vector3::new(2.0, 5.0, 0.0));
+        assert!((force - Vector3::new(-2.0, 1.0, -3.0)).magnitude() < 1e-6);
+    }
+
+    #[test]
+    fn radial_pushes_away_from_its_center() {
+        let mut radial = Radial {
+            center: Vector3::new(0.0, 0.0, 1.0),
+            strength: 4.0,
+        };
+        let mut bodies = BodySet::new();
+        let body = bodies.insert(body_at(Vector3::new(0.0, 0.0, 3.0)));
+        radial.apply(&context(), body, &mut bodies);
+        assert_eq!(bodies[body].force_accumulator, Vector3::new(0.0, 0.0, 4.0));
+
+        // A negative strength pulls bodies in
+        radial.strength = -4.0;
+        radial.apply(&context(), body, &mut bodies);
+        assert_eq!(bodies[body].force_accumulator, Vector3::zero());
+    }
+}
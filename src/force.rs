@@ -1,16 +1,91 @@
-use crate::{BodySet, Handle, Real, Vector3};
+use crate::{
+    noise::{self, Random},
+    Body, BodyHandle, BodySet, BodyType, CollisionFilter, ForceGeneratorHandle, PeriodicBoundary,
+    Real, Shape, Vector3, WaterSurface,
+};
+use std::{
+    any::Any,
+    collections::{BTreeMap, HashMap},
+};
+
+/// Describes the tick that force generators are being applied in.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ForceContext {
+    /// The length of the tick in seconds
+    pub duration: Real,
+
+    /// The simulated time that had passed before the tick began
+    pub elapsed: Real,
+
+    /// The number of ticks completed before this one
+    pub tick: u64,
+
+    /// The periodic boundary of the world, if it has one
+    pub boundary: Option<PeriodicBoundary>,
+}
+
+impl ForceContext {
+    /// The offset from one position to another, taking the shortest
+    /// way around the periodic boundary if there is one.
+    pub fn offset(&self, from: Vector3, to: Vector3) -> Vector3 {
+        match self.boundary {
+            Some(boundary) => boundary.minimum_image(to - from),
+            None => to - from,
+        }
+    }
+}
 
 pub trait ForceGenerator {
-    fn apply(&self, duration: Real, body_handle: Handle, bodies: &mut BodySet);
+    fn apply(&mut self, context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet);
+
+    /// Advances any internal state of the generator, such as timers or accumulators.
+    ///
+    /// This is called once per tick, before the generator is applied to any bodies,
+    /// with the handles of every body the generator is registered for.
+    fn update(
+        &mut self,
+        _context: &ForceContext,
+        _bodies: &BodySet,
+        _registered_bodies: &[BodyHandle],
+    ) {
+    }
+
+    /// Whether the generator depends on the given body, such as the other end
+    /// of a spring, in which case it is removed from the world along with the body.
+    fn references_body(&self, _body_handle: BodyHandle) -> bool {
+        false
+    }
+
+    /// The generator as `Any`, for generators with state worth reading or changing
+    /// after they've been added to a world. Other generators can't be downcast.
+    fn as_any(&self) -> Option<&dyn Any> {
+        None
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        None
+    }
+}
+
+impl dyn ForceGenerator {
+    /// Borrows the generator as its concrete type, such as to read the state
+    /// of a generator added to a world. See `ForceGenerator::as_any`.
+    pub fn downcast_ref<T: ForceGenerator + Any>(&self) -> Option<&T> {
+        self.as_any()?.downcast_ref()
+    }
+
+    pub fn downcast_mut<T: ForceGenerator + Any>(&mut self) -> Option<&mut T> {
+        self.as_any_mut()?.downcast_mut()
+    }
 }
 
 pub struct ForceRegistration {
-    pub generator_handle: Handle,
-    pub bodies: Vec<Handle>,
+    pub generator_handle: ForceGeneratorHandle,
+    pub bodies: Vec<BodyHandle>,
 }
 
 impl ForceRegistration {
-    pub fn new(generator_handle: Handle, bodies: Vec<Handle>) -> Self {
+    pub fn new(generator_handle: ForceGeneratorHandle, bodies: Vec<BodyHandle>) -> Self {
         Self {
             generator_handle,
             bodies,
@@ -18,6 +93,47 @@ impl ForceRegistration {
     }
 }
 
+/// Applies a force generator to every dynamic body in the world,
+/// including bodies added after the registration.
+pub struct GlobalForceRegistration {
+    pub generator_handle: ForceGeneratorHandle,
+
+    /// Restricts the generator to the bodies this filter can interact with
+    pub collision_filter: CollisionFilter,
+}
+
+impl GlobalForceRegistration {
+    pub fn new(generator_handle: ForceGeneratorHandle, collision_filter: CollisionFilter) -> Self {
+        Self {
+            generator_handle,
+            collision_filter,
+        }
+    }
+
+    /// Checks whether the generator applies to a body.
+    pub fn applies_to(&self, body: &Body) -> bool {
+        body.body_type == BodyType::Dynamic
+            && self.collision_filter.can_interact(&body.collision_filter)
+    }
+}
+
+/// Adapts a closure into a force generator, for one-off forces
+/// that don't warrant a dedicated type.
+///
+/// The closure is called with each registered body on every tick.
+pub struct FnForce<F>(pub F);
+
+impl<F> ForceGenerator for FnForce<F>
+where
+    F: FnMut(&ForceContext, &mut Body),
+{
+    fn apply(&mut self, context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        if let Some(body) = bodies.get_mut(body_handle) {
+            (self.0)(context, body);
+        }
+    }
+}
+
 pub struct Gravity {
     pub force: Vector3,
 }
@@ -39,7 +155,7 @@ impl Default for Gravity {
 }
 
 impl ForceGenerator for Gravity {
-    fn apply(&self, _duration: Real, body_handle: Handle, bodies: &mut BodySet) {
+    fn apply(&mut self, _context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
         let body = match bodies.get_mut(body_handle) {
             Some(body) => body,
             None => return,
@@ -48,59 +164,435 @@ impl ForceGenerator for Gravity {
         if body.has_infinite_mass() {
             return;
         }
-        let force = self.force * body.mass();
+        let force = self.force * (body.mass() * body.gravity_scale);
         body.add_force(&force);
     }
 }
 
-#[derive(Default)]
+/// Slows bodies down as they move through air or another fluid.
+///
+/// The drag combines a linear term, a quadratic term and, when the air has density,
+/// the aerodynamic drag `½ρv²CdA` using the body's drag coefficient and frontal area.
+/// The drag on a body is never strong enough to reverse its velocity
+/// within a single tick, however long the tick is.
+#[derive(Default, Copy, Clone)]
 pub struct Drag {
+    /// The drag force per unit of speed
     pub k1: Real,
+
+    /// The drag force per unit of squared speed
     pub k2: Real,
+
+    /// The density of the air at the reference altitude in kilograms per cubic meter
+    pub air_density: Real,
+
+    /// The altitude along the y axis at which the air has its reference density
+    pub reference_altitude: Real,
+
+    /// The rise in altitude over which the air thins by a factor of e,
+    /// or zero to keep the same density at every altitude
+    pub scale_height: Real,
+}
+
+impl Drag {
+    /// The density of air at sea level on earth in kilograms per cubic meter
+    pub const SEA_LEVEL_AIR_DENSITY: Real = 1.225;
+
+    /// The scale height of earth's atmosphere in meters
+    pub const EARTH_SCALE_HEIGHT: Real = 8500.0;
+
+    pub fn new(k1: Real, k2: Real) -> Self {
+        Self {
+            k1,
+            k2,
+            ..Self::default()
+        }
+    }
+
+    /// Aerodynamic drag through earth's atmosphere, with sea level at an altitude of zero.
+    pub fn air() -> Self {
+        Self {
+            air_density: Self::SEA_LEVEL_AIR_DENSITY,
+            scale_height: Self::EARTH_SCALE_HEIGHT,
+            ..Self::default()
+        }
+    }
+
+    /// The density of the air at the given altitude.
+    pub fn air_density_at(&self, altitude: Real) -> Real {
+        if self.scale_height <= 0.0 {
+            return self.air_density;
+        }
+        self.air_density * (-(altitude - self.reference_altitude) / self.scale_height).exp()
+    }
+
+    /// The drag force on a body moving at the given velocity relative to the air.
+    pub fn force(&self, body: &Body, relative_velocity: Vector3, duration: Real) -> Vector3 {
+        let speed = relative_velocity.magnitude();
+        if !(speed > 0.0 && speed.is_finite()) {
+            // A body at rest relative to the air has no direction to be dragged in
+            return Vector3::zero();
+        }
+
+        let area = body.frontal_area();
+        let aerodynamic = if area.is_finite() && self.air_density > 0.0 {
+            0.5 * self.air_density_at(body.position.y) * body.drag_coefficient * area
+        } else {
+            0.0
+        };
+        let mut magnitude = self.k1 * speed + (self.k2 + aerodynamic) * speed.powi(2);
+
+        // Stop the body relative to the air at most, rather than sending it backwards
+        if !body.has_infinite_mass() && duration > 0.0 {
+            magnitude = magnitude.min(speed * body.mass() / duration);
+        }
+
+        relative_velocity * (-magnitude / speed)
+    }
 }
 
 impl ForceGenerator for Drag {
-    fn apply(&self, _duration: Real, body_handle: Handle, bodies: &mut BodySet) {
+    fn apply(&mut self, context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
         let body = match bodies.get_mut(body_handle) {
             Some(body) => body,
             None => return,
         };
 
-        let mut drag_coefficient = body.velocity.magnitude();
-        drag_coefficient = self.k1 * drag_coefficient + self.k2 * drag_coefficient.powi(2);
-        let force = body.velocity.normalize() * -drag_coefficient;
+        let force = self.force(body, body.velocity, context.duration);
         body.add_force(&force);
     }
 }
 
-pub struct Spring {
-    pub end_body_handle: Handle, // FIXME: Replace this with a handle
-    pub spring_constant: Real,
-    pub rest_length: Real,
+/// Coulomb's constant in newton square meters per square coulomb.
+pub const COULOMB_CONSTANT: Real = 8.987_551e9;
+
+/// Makes every charged body the generator is registered for push or pull on every
+/// other one, with like charges repelling and opposite charges attracting.
+///
+/// Bodies with infinite mass still act on others, so they can be used as fixed charges.
+pub struct Coulomb {
+    pub coulomb_constant: Real,
+
+    /// Smooths out the force between charges that pass close to each other
+    pub softening: Real,
+
+    forces: BTreeMap<BodyHandle, Vector3>,
 }
 
-impl ForceGenerator for Spring {
-    fn apply(&self, _duration: Real, body_handle: Handle, bodies: &mut BodySet) {
-        let end_body_position = {
-            let end_body = match bodies.get(self.end_body_handle) {
-                Some(end_body) => end_body,
-                None => return,
-            };
-            end_body.position
+impl Coulomb {
+    pub fn new(softening: Real) -> Self {
+        Self {
+            coulomb_constant: COULOMB_CONSTANT,
+            softening,
+            forces: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for Coulomb {
+    fn default() -> Self {
+        Self::new(0.0)
+    }
+}
+
+impl ForceGenerator for Coulomb {
+    fn apply(&mut self, _context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        if let (Some(force), Some(body)) =
+            (self.forces.get(&body_handle), bodies.get_mut(body_handle))
+        {
+            body.add_force(force);
+        }
+    }
+
+    fn update(
+        &mut self,
+        _context: &ForceContext,
+        bodies: &BodySet,
+        registered_bodies: &[BodyHandle],
+    ) {
+        self.forces.clear();
+
+        let charges: Vec<_> = registered_bodies
+            .iter()
+            .filter_map(|&body_handle| {
+                let body = bodies.get(body_handle)?;
+                if body.charge == 0.0 {
+                    return None;
+                }
+                Some((body_handle, body.position, body.charge))
+            })
+            .collect();
+
+        let mut forces = vec![Vector3::zero(); charges.len()];
+        for (i, &(_, position, charge)) in charges.iter().enumerate() {
+            for (j, &(_, other_position, other_charge)) in charges.iter().enumerate().skip(i + 1) {
+                let offset = position - other_position;
+                let distance_squared = offset.magnitude_squared() + self.softening.powi(2);
+                if distance_squared <= 0.0 {
+                    continue;
+                }
+
+                let magnitude = self.coulomb_constant * charge * other_charge
+                    / (distance_squared * distance_squared.sqrt());
+                let force = offset * magnitude;
+                forces[i] += force;
+                forces[j] -= force;
+            }
+        }
+
+        self.forces = charges
+            .into_iter()
+            .map(|(body_handle, _, _)| body_handle)
+            .zip(forces)
+            .collect();
+    }
+}
+
+/// A vector field as a function of position and time.
+pub type FieldFunction = Box<dyn Fn(Vector3, Real) -> Vector3>;
+
+/// An electric or magnetic field.
+pub enum Field {
+    /// The same everywhere and at all times
+    Uniform(Vector3),
+
+    /// Given by a function of position and time
+    Function(FieldFunction),
+}
+
+impl Field {
+    pub fn sample(&self, position: Vector3, time: Real) -> Vector3 {
+        match self {
+            Self::Uniform(field) => *field,
+            Self::Function(field) => field(position, time),
+        }
+    }
+}
+
+impl Default for Field {
+    fn default() -> Self {
+        Self::Uniform(Vector3::zero())
+    }
+}
+
+/// Pushes charged bodies with the Lorentz force of an electric and a magnetic field.
+///
+/// The electric field pushes along its direction, while the magnetic field pushes
+/// at right angles to both itself and the body's velocity, curving the body's path.
+#[derive(Default)]
+pub struct Lorentz {
+    /// The electric field in volts per meter
+    pub electric: Field,
+
+    /// The magnetic field in teslas
+    pub magnetic: Field,
+}
+
+impl Lorentz {
+    pub fn new(electric: Field, magnetic: Field) -> Self {
+        Self { electric, magnetic }
+    }
+
+    /// The force on a charge moving with the given velocity through the fields.
+    pub fn force(&self, charge: Real, position: Vector3, velocity: Vector3, time: Real) -> Vector3 {
+        let electric = self.electric.sample(position, time);
+        let magnetic = self.magnetic.sample(position, time);
+        (electric + velocity.cross(magnetic)) * charge
+    }
+}
+
+impl ForceGenerator for Lorentz {
+    fn apply(&mut self, context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        let body = match bodies.get_mut(body_handle) {
+            Some(body) => body,
+            None => return,
         };
 
+        if body.charge == 0.0 {
+            return;
+        }
+
+        let force = self.force(body.charge, body.position, body.velocity, context.elapsed);
+        body.add_force(&force);
+    }
+}
+
+/// Pushes bodies around with a spatially varying wind field.
+///
+/// Bodies experience drag against their velocity relative to the wind, so a body
+/// at rest is carried along while a body matching the wind feels nothing.
+/// The field combines a steady wind, gusts that vary its strength over time and
+/// swirling turbulence that varies over space, drifting along with the wind.
+pub struct Wind {
+    /// The steady wind velocity
+    pub velocity: Vector3,
+
+    /// The drag model applied to the airspeed of each body
+    pub drag: Drag,
+
+    /// How much gusts change the wind speed, as a fraction of the steady wind speed
+    pub gust_strength: Real,
+
+    /// How many gusts occur per second, roughly
+    pub gust_frequency: Real,
+
+    /// The average speed of turbulent eddies. The fastest eddies reach about three times this.
+    pub turbulence_strength: Real,
+
+    /// The size of turbulent eddies in meters
+    pub turbulence_scale: Real,
+
+    /// How quickly the shape of the turbulence changes over time
+    pub turbulence_evolution: Real,
+
+    /// Varies the generated gusts and turbulence
+    pub seed: u32,
+}
+
+impl Wind {
+    pub fn new(velocity: Vector3, drag: Drag) -> Self {
+        Self {
+            velocity,
+            drag,
+            gust_strength: 0.5,
+            gust_frequency: 0.2,
+            turbulence_strength: 1.0,
+            turbulence_scale: 10.0,
+            turbulence_evolution: 0.1,
+            seed: 0,
+        }
+    }
+
+    /// The velocity of the wind at the given position and time.
+    pub fn sample(&self, position: Vector3, time: Real) -> Vector3 {
+        let gust = noise::value_noise(
+            Vector3::new(time * self.gust_frequency, 0.0, 0.0),
+            self.seed,
+        );
+        let steady = self.velocity * (1.0 + self.gust_strength * gust);
+
+        if self.turbulence_scale <= 0.0 {
+            return steady;
+        }
+
+        // Eddies are carried along by the wind while slowly changing shape
+        let drifted_position = position - self.velocity * time;
+        let sample_point = drifted_position / self.turbulence_scale
+            + Vector3::new(0.0, time * self.turbulence_evolution, 0.0);
+        let turbulence = noise::curl_noise(sample_point, self.seed.wrapping_add(1));
+
+        steady + turbulence * self.turbulence_strength
+    }
+}
+
+impl ForceGenerator for Wind {
+    fn apply(&mut self, context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
         let body = match bodies.get_mut(body_handle) {
             Some(body) => body,
             None => return,
         };
 
-        let force = body.position - end_body_position;
-        let magnitude = (force.magnitude() - self.rest_length).abs() * self.spring_constant;
-        let force = force.normalize() * -magnitude;
+        let wind = self.sample(body.position, context.elapsed);
+        let force = self
+            .drag
+            .force(body, body.velocity - wind, context.duration);
         body.add_force(&force);
     }
 }
 
+/// A spring connecting two bodies.
+///
+/// Register the spring for one of its ends only: each application pushes
+/// on both bodies with equal and opposite forces, so momentum is conserved.
+pub struct Spring {
+    pub end_body_handle: BodyHandle,
+    pub spring_constant: Real,
+    pub rest_length: Real,
+}
+
+impl ForceGenerator for Spring {
+    fn apply(&mut self, _context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        apply_spring(
+            bodies,
+            body_handle,
+            self.end_body_handle,
+            self.spring_constant,
+            self.rest_length,
+            0.0,
+        );
+    }
+
+    fn references_body(&self, body_handle: BodyHandle) -> bool {
+        self.end_body_handle == body_handle
+    }
+}
+
+/// A spring connecting two bodies that also resists the
+/// bodies moving towards or away from each other.
+///
+/// Like `Spring`, it should be registered for one of its ends only.
+pub struct DampedSpring {
+    pub end_body_handle: BodyHandle,
+    pub spring_constant: Real,
+    pub rest_length: Real,
+
+    /// The force per unit of relative speed along the spring's axis
+    pub damping: Real,
+}
+
+impl ForceGenerator for DampedSpring {
+    fn apply(&mut self, _context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        apply_spring(
+            bodies,
+            body_handle,
+            self.end_body_handle,
+            self.spring_constant,
+            self.rest_length,
+            self.damping,
+        );
+    }
+
+    fn references_body(&self, body_handle: BodyHandle) -> bool {
+        self.end_body_handle == body_handle
+    }
+}
+
+/// Applies equal and opposite spring forces to a pair of bodies.
+fn apply_spring(
+    bodies: &mut BodySet,
+    body_handle: BodyHandle,
+    end_body_handle: BodyHandle,
+    spring_constant: Real,
+    rest_length: Real,
+    damping: Real,
+) {
+    if body_handle == end_body_handle {
+        return;
+    }
+
+    let (body, end_body) = match bodies.get2_mut(body_handle, end_body_handle) {
+        (Some(body), Some(end_body)) => (body, end_body),
+        _ => return,
+    };
+
+    let offset = body.position - end_body.position;
+    let length = offset.magnitude();
+    if length <= Real::EPSILON {
+        // The spring has no direction to push along
+        return;
+    }
+    let direction = offset / length;
+
+    // Positive when stretched, so the spring pulls the ends together,
+    // and negative when compressed, so it pushes them apart
+    let extension = length - rest_length;
+    let separating_speed = (body.velocity - end_body.velocity).dot(direction);
+
+    let force = direction * -(spring_constant * extension + damping * separating_speed);
+    body.add_force(&force);
+    end_body.add_force(&force.inverse());
+}
+
 pub struct AnchoredSpring {
     pub anchor: Vector3,
     pub spring_constant: Real,
@@ -108,26 +600,95 @@ pub struct AnchoredSpring {
 }
 
 impl ForceGenerator for AnchoredSpring {
-    fn apply(&self, _duration: Real, body_handle: Handle, bodies: &mut BodySet) {
+    fn apply(&mut self, _context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
         let body = match bodies.get_mut(body_handle) {
             Some(body) => body,
             None => return,
         };
         let force = body.position - self.anchor;
-        let magnitude = (self.rest_length - force.magnitude()) * self.spring_constant;
+        let magnitude = (force.magnitude() - self.rest_length) * self.spring_constant;
         let force = force.normalize() * -magnitude;
         body.add_force(&force);
     }
 }
 
+/// A stiff spring attaching a body to an anchor point.
+///
+/// Rather than applying Hooke's law directly, which becomes unstable when the spring is
+/// stiff relative to the tick duration, the generator predicts where the body would be
+/// at the end of the tick using the closed form solution of a damped harmonic oscillator
+/// and applies the force needed to get it there. This remains stable at any stiffness.
+///
+/// The spring has a rest length of zero. Both constants are per unit of mass, so bodies of
+/// any mass follow the same motion, and the generator ignores any other forces on the body.
+pub struct StiffSpring {
+    pub anchor: Vector3,
+    pub spring_constant: Real,
+    pub damping: Real,
+}
+
+impl StiffSpring {
+    /// Solves for the offset from the anchor after the given duration, starting
+    /// from the given offset and velocity.
+    pub fn predict(&self, offset: Vector3, velocity: Vector3, duration: Real) -> Vector3 {
+        let k = self.spring_constant;
+        let d = self.damping;
+        let decay = (-0.5 * d * duration).exp();
+        let discriminant = d.powi(2) - 4.0 * k;
+
+        if discriminant.abs() <= Real::EPSILON * d.powi(2).max(4.0 * k) {
+            // Critically damped
+            let rate = velocity + offset * (0.5 * d);
+            (offset + rate * duration) * decay
+        } else if discriminant < 0.0 {
+            // Underdamped, oscillating about the anchor while decaying
+            let frequency = 0.5 * (-discriminant).sqrt();
+            let c = (velocity + offset * (0.5 * d)) / frequency;
+            let angle = frequency * duration;
+            (offset * angle.cos() + c * angle.sin()) * decay
+        } else {
+            // Overdamped, returning to the anchor without oscillating
+            let root = discriminant.sqrt();
+            let fast_rate = 0.5 * (-d - root);
+            let slow_rate = 0.5 * (-d + root);
+            let slow = (velocity - offset * fast_rate) / (slow_rate - fast_rate);
+            let fast = offset - slow;
+            slow * (slow_rate * duration).exp() + fast * (fast_rate * duration).exp()
+        }
+    }
+}
+
+impl ForceGenerator for StiffSpring {
+    fn apply(&mut self, context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        let body = match bodies.get_mut(body_handle) {
+            Some(body) => body,
+            None => return,
+        };
+
+        if body.has_infinite_mass() || context.duration <= 0.0 {
+            return;
+        }
+
+        // Integration moves the body by its current velocity before forces take effect,
+        // so aim the new velocity at where the spring carries the body from there
+        let offset = body.position + body.velocity * context.duration - self.anchor;
+        let target = self.predict(offset, body.velocity, context.duration);
+        let target_velocity = (target - offset) / context.duration;
+
+        let acceleration = (target_velocity - body.velocity - body.acceleration) / context.duration;
+        let force = acceleration * body.mass();
+        body.add_force(&force);
+    }
+}
+
 pub struct Bungee {
-    pub end_body_handle: Handle,
+    pub end_body_handle: BodyHandle,
     pub spring_constant: Real,
     pub rest_length: Real,
 }
 
 impl ForceGenerator for Bungee {
-    fn apply(&self, _duration: Real, body_handle: Handle, bodies: &mut BodySet) {
+    fn apply(&mut self, _context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
         let end_body_position = {
             let end_body = match bodies.get(self.end_body_handle) {
                 Some(end_body) => end_body,
@@ -146,10 +707,14 @@ impl ForceGenerator for Bungee {
         if magnitude < self.rest_length {
             return;
         }
-        let magnitude = (self.rest_length - magnitude) * self.spring_constant;
+        let magnitude = (magnitude - self.rest_length) * self.spring_constant;
         let force = force.normalize() * -magnitude;
         body.add_force(&force);
     }
+
+    fn references_body(&self, body_handle: BodyHandle) -> bool {
+        self.end_body_handle == body_handle
+    }
 }
 
 pub struct AnchoredBungee {
@@ -159,7 +724,7 @@ pub struct AnchoredBungee {
 }
 
 impl ForceGenerator for AnchoredBungee {
-    fn apply(&self, _duration: Real, body_handle: Handle, bodies: &mut BodySet) {
+    fn apply(&mut self, _context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
         let body = match bodies.get_mut(body_handle) {
             Some(body) => body,
             None => return,
@@ -176,38 +741,1233 @@ impl ForceGenerator for AnchoredBungee {
     }
 }
 
+/// Floats bodies in a body of water.
+///
+/// Bodies are treated as spheres with the radius and volume of their shape, and are pushed
+/// out of the water by the weight of the liquid they displace. Submerged bodies also feel
+/// drag against their velocity relative to the water's current, in proportion to how much
+/// of them is submerged.
+///
+/// Point particles, which have a radius of zero, are treated as spheres with their own
+/// point volume.
 pub struct Buoyancy {
-    pub max_depth: Real,
-    pub volume: Real,
-    pub water_height: Real,
+    pub surface: WaterSurface,
+
+    /// The density of the liquid in kilograms per cubic meter
     pub liquid_density: Real,
+
+    /// The strength of gravity pulling on the displaced liquid
+    pub gravity: Real,
+
+    /// The drag force per unit of speed through the water on a fully submerged body
+    pub drag: Real,
+
+    /// The velocity of the water
+    pub current: Vector3,
+}
+
+impl Buoyancy {
+    /// Creates buoyancy for fresh water under earth gravity.
+    pub fn new(surface: WaterSurface) -> Self {
+        Self {
+            surface,
+            liquid_density: 1000.0,
+            gravity: -Gravity::earth_gravity().y,
+            drag: 0.0,
+            current: Vector3::zero(),
+        }
+    }
+
+    /// The fraction of a sphere's volume lying below the surface,
+    /// given the depth of its center below the surface.
+    pub fn submerged_fraction(depth: Real, radius: Real) -> Real {
+        if depth >= radius {
+            return 1.0;
+        }
+        if depth <= -radius {
+            return 0.0;
+        }
+
+        // The submerged part is a spherical cap
+        let height = depth + radius;
+        height.powi(2) * (3.0 * radius - height) / (4.0 * radius.powi(3))
+    }
 }
 
 impl ForceGenerator for Buoyancy {
-    fn apply(&self, _duration: Real, body_handle: Handle, bodies: &mut BodySet) {
+    fn apply(&mut self, context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
         let body = match bodies.get_mut(body_handle) {
             Some(body) => body,
             None => return,
         };
 
-        let depth = body.position.y;
-        let out_of_water = depth >= self.water_height + self.max_depth;
-        if out_of_water {
+        if body.has_infinite_mass() {
+            return;
+        }
+
+        if let Shape::Plane { .. } = body.shape {
+            return;
+        }
+
+        // Point particles float like a sphere of their point volume
+        let depth = self.surface.depth(body.position, context.elapsed);
+        let fraction = Self::submerged_fraction(depth, body.equivalent_radius());
+        if fraction <= 0.0 {
             return;
         }
-        let mut force = Vector3::zero();
 
-        let at_max_depth = depth <= self.water_height - self.max_depth;
-        if at_max_depth {
-            force.y = self.liquid_density * self.volume;
+        let displaced = body.volume() * fraction;
+        let buoyancy = self.surface.up() * (self.liquid_density * displaced * self.gravity);
+        let drag = (self.current - body.velocity) * (self.drag * fraction);
+        body.add_force(&(buoyancy + drag));
+    }
+}
+
+/// An explosion that evolves over time.
+///
+/// The explosion begins with a brief implosion that pulls nearby bodies in towards the
+/// detonation. It then releases an expanding shockwave that pushes bodies outwards as it
+/// passes them, along with a convection chimney that lifts bodies above the detonation.
+/// Both the shockwave and the convection weaken linearly over their durations.
+pub struct Explosion {
+    pub detonation: Vector3,
+
+    /// Bodies closer than this to the detonation aren't affected by the implosion
+    pub implosion_min_radius: Real,
+
+    /// Bodies further than this from the detonation aren't affected by the implosion
+    pub implosion_max_radius: Real,
+    pub implosion_duration: Real,
+    pub implosion_force: Real,
+
+    /// How quickly the shockwave expands, in meters per second
+    pub shockwave_speed: Real,
+
+    /// The depth of the shockwave's shell. Faster shockwaves need thicker shells,
+    /// otherwise they can pass over bodies between ticks without affecting them.
+    pub shockwave_thickness: Real,
+    pub peak_concussion_force: Real,
+    pub concussion_duration: Real,
+
+    pub peak_convection_force: Real,
+    pub chimney_radius: Real,
+    pub chimney_height: Real,
+    pub convection_duration: Real,
+
+    started: Option<Real>,
+    elapsed: Real,
+}
+
+impl Explosion {
+    pub fn new(detonation: Vector3) -> Self {
+        Self {
+            detonation,
+            implosion_min_radius: 0.5,
+            implosion_max_radius: 10.0,
+            implosion_duration: 0.1,
+            implosion_force: 50.0,
+            shockwave_speed: 50.0,
+            shockwave_thickness: 5.0,
+            peak_concussion_force: 500.0,
+            concussion_duration: 1.0,
+            peak_convection_force: 100.0,
+            chimney_radius: 3.0,
+            chimney_height: 10.0,
+            convection_duration: 3.0,
+            started: None,
+            elapsed: 0.0,
+        }
+    }
+
+    /// The time from the start of the explosion's first tick to the start of its latest one
+    pub fn elapsed(&self) -> Real {
+        self.elapsed
+    }
+
+    /// Whether every phase of the explosion has ended
+    pub fn is_finished(&self) -> bool {
+        self.elapsed
+            > self.implosion_duration + self.concussion_duration.max(self.convection_duration)
+    }
+
+    fn implosion_force(&self, offset: Vector3) -> Vector3 {
+        let distance = offset.magnitude();
+        if distance < self.implosion_min_radius || distance > self.implosion_max_radius {
+            return Vector3::zero();
+        }
+        offset.normalize() * -self.implosion_force
+    }
+
+    fn concussion_force(&self, offset: Vector3, blast_time: Real) -> Vector3 {
+        if blast_time > self.concussion_duration {
+            return Vector3::zero();
+        }
+
+        let front = self.shockwave_speed * blast_time;
+        let back = (front - self.shockwave_thickness).max(0.0);
+        let distance = offset.magnitude();
+        if distance < back || distance > front || front <= back {
+            return Vector3::zero();
+        }
+
+        // The shell is strongest at its leading edge
+        let falloff = (distance - back) / (front - back);
+        let decay = 1.0 - blast_time / self.concussion_duration;
+        let direction = if distance > 0.0 {
+            offset / distance
         } else {
-            // partially submerged
-            force.y =
-                self.liquid_density * self.volume * (depth - self.max_depth - self.water_height)
-                    / 2.0
-                    * self.max_depth;
+            Vector3::y()
+        };
+        direction * self.peak_concussion_force * falloff * decay
+    }
+
+    fn convection_force(&self, offset: Vector3, blast_time: Real) -> Vector3 {
+        if blast_time > self.convection_duration {
+            return Vector3::zero();
         }
 
+        let horizontal_distance = (offset.x.powi(2) + offset.z.powi(2)).sqrt();
+        let inside_chimney = offset.y >= 0.0
+            && offset.y <= self.chimney_height
+            && horizontal_distance < self.chimney_radius;
+        if !inside_chimney {
+            return Vector3::zero();
+        }
+
+        // The chimney is strongest along its axis
+        let falloff = 1.0 - horizontal_distance / self.chimney_radius;
+        let decay = 1.0 - blast_time / self.convection_duration;
+        Vector3::y() * self.peak_convection_force * falloff * decay
+    }
+}
+
+impl ForceGenerator for Explosion {
+    fn apply(&mut self, _context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        let body = match bodies.get_mut(body_handle) {
+            Some(body) => body,
+            None => return,
+        };
+
+        if body.has_infinite_mass() {
+            return;
+        }
+
+        let offset = body.position - self.detonation;
+        let force = if self.elapsed <= self.implosion_duration {
+            self.implosion_force(offset)
+        } else {
+            let blast_time = self.elapsed - self.implosion_duration;
+            self.concussion_force(offset, blast_time) + self.convection_force(offset, blast_time)
+        };
         body.add_force(&force);
     }
+
+    fn update(
+        &mut self,
+        context: &ForceContext,
+        _bodies: &BodySet,
+        _registered_bodies: &[BodyHandle],
+    ) {
+        // The first tick runs at the moment of detonation
+        let started = *self.started.get_or_insert(context.elapsed);
+        self.elapsed = context.elapsed - started;
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        Some(self)
+    }
+}
+
+/// How strongly each behavior of `Flocking` steers bodies.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SteeringWeights {
+    pub seek: Real,
+    pub flee: Real,
+    pub arrive: Real,
+    pub wander: Real,
+    pub separation: Real,
+    pub alignment: Real,
+    pub cohesion: Real,
+}
+
+impl Default for SteeringWeights {
+    /// Plain flocking, without any targets.
+    fn default() -> Self {
+        Self {
+            seek: 0.0,
+            flee: 0.0,
+            arrive: 0.0,
+            wander: 0.0,
+            separation: 1.5,
+            alignment: 1.0,
+            cohesion: 1.0,
+        }
+    }
+}
+
+/// Steers bodies like a flock of birds or a school of fish.
+///
+/// Each behavior asks for a velocity and steers with the force needed to reach it:
+///
+/// * Seek heads for the target at full speed, while arrive slows down to stop on it.
+/// * Flee runs from the threat while it's within the flee radius.
+/// * Wander drifts in a direction that changes randomly over time.
+/// * Separation avoids crowding neighbors within the separation radius, alignment matches
+///   the heading of neighbors within the neighbor radius and cohesion moves towards them.
+///
+/// Every body the generator is registered for is part of the same flock. Each behavior's
+/// force is clamped to the maximum force before being weighted, and so is their sum.
+pub struct Flocking {
+    pub weights: SteeringWeights,
+
+    /// The place to seek or arrive at
+    pub target: Option<Vector3>,
+
+    /// The place to flee from
+    pub threat: Option<Vector3>,
+
+    /// The speed bodies steer towards
+    pub max_speed: Real,
+
+    /// The largest steering force applied to a body
+    pub max_force: Real,
+
+    /// How close other bodies must be to count as neighbors
+    pub neighbor_radius: Real,
+
+    /// How close neighbors must be before bodies steer away from them
+    pub separation_radius: Real,
+
+    /// The distance from the target at which arriving bodies begin to slow down
+    pub slowing_radius: Real,
+
+    /// How close the threat must be before bodies flee from it
+    pub flee_radius: Real,
+
+    /// How far ahead of a body its wander sphere is
+    pub wander_distance: Real,
+
+    /// The radius of the wander sphere, which limits how sharply wandering bodies turn
+    pub wander_radius: Real,
+
+    /// How quickly the wander direction changes, in wander sphere radii per second
+    pub wander_jitter: Real,
+
+    random: Random,
+    wander_offsets: BTreeMap<BodyHandle, Vector3>,
+    forces: BTreeMap<BodyHandle, Vector3>,
+}
+
+impl Flocking {
+    pub fn new(max_speed: Real, max_force: Real) -> Self {
+        Self {
+            weights: SteeringWeights::default(),
+            target: None,
+            threat: None,
+            max_speed,
+            max_force,
+            neighbor_radius: 5.0,
+            separation_radius: 1.5,
+            slowing_radius: 5.0,
+            flee_radius: Real::INFINITY,
+            wander_distance: 2.0,
+            wander_radius: 1.0,
+            wander_jitter: 4.0,
+            random: Random::default(),
+            wander_offsets: BTreeMap::new(),
+            forces: BTreeMap::new(),
+        }
+    }
+
+    /// Seeds the random numbers used for wandering.
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.random = Random::new(seed);
+        self
+    }
+
+    /// The force that changes a velocity into the desired one, clamped to the maximum force.
+    fn steer(&self, desired: Vector3, velocity: Vector3) -> Vector3 {
+        (desired - velocity).clamp_magnitude(self.max_force)
+    }
+
+    fn seek(&self, position: Vector3, velocity: Vector3, target: Vector3) -> Vector3 {
+        self.steer((target - position).normalize() * self.max_speed, velocity)
+    }
+
+    fn flee(&self, position: Vector3, velocity: Vector3, threat: Vector3) -> Vector3 {
+        let offset = position - threat;
+        if offset.magnitude_squared() > self.flee_radius.powi(2) {
+            return Vector3::zero();
+        }
+        self.steer(offset.normalize() * self.max_speed, velocity)
+    }
+
+    fn arrive(&self, position: Vector3, velocity: Vector3, target: Vector3) -> Vector3 {
+        let offset = target - position;
+        let distance = offset.magnitude();
+        if distance <= 0.0 {
+            return self.steer(Vector3::zero(), velocity);
+        }
+        let speed = if distance < self.slowing_radius {
+            self.max_speed * distance / self.slowing_radius
+        } else {
+            self.max_speed
+        };
+        self.steer(offset * (speed / distance), velocity)
+    }
+
+    /// Moves a body's point on its wander sphere randomly and steers towards it.
+    fn wander(
+        &mut self,
+        body_handle: BodyHandle,
+        position: Vector3,
+        velocity: Vector3,
+        duration: Real,
+    ) -> Vector3 {
+        let jitter = Vector3::new(
+            self.random.signed(),
+            self.random.signed(),
+            self.random.signed(),
+        ) * (self.wander_jitter * duration);
+        let offset = self
+            .wander_offsets
+            .get(&body_handle)
+            .copied()
+            .unwrap_or_else(Vector3::x);
+        let mut offset = (offset + jitter).normalize();
+        if offset.magnitude_squared() == 0.0 {
+            offset = Vector3::x();
+        }
+        self.wander_offsets.insert(body_handle, offset);
+
+        let heading = if velocity.magnitude_squared() > 0.0 {
+            velocity.normalize()
+        } else {
+            offset
+        };
+        let target = position + heading * self.wander_distance + offset * self.wander_radius;
+        self.seek(position, velocity, target)
+    }
+
+    /// The steering forces from a body's neighbors as separation, alignment and cohesion.
+    fn flock(
+        &self,
+        position: Vector3,
+        velocity: Vector3,
+        neighbors: &[(Vector3, Vector3)],
+    ) -> (Vector3, Vector3, Vector3) {
+        if neighbors.is_empty() {
+            return (Vector3::zero(), Vector3::zero(), Vector3::zero());
+        }
+
+        let mut away = Vector3::zero();
+        let mut heading = Vector3::zero();
+        let mut center = Vector3::zero();
+        for &(neighbor_position, neighbor_velocity) in neighbors {
+            let offset = position - neighbor_position;
+            let distance_squared = offset.magnitude_squared();
+            if distance_squared > 0.0 && distance_squared < self.separation_radius.powi(2) {
+                // Closer neighbors push harder
+                away += offset / distance_squared;
+            }
+            heading += neighbor_velocity;
+            center += neighbor_position;
+        }
+
+        let separation = if away.magnitude_squared() > 0.0 {
+            self.steer(away.normalize() * self.max_speed, velocity)
+        } else {
+            Vector3::zero()
+        };
+        let alignment = if heading.magnitude_squared() > 0.0 {
+            self.steer(heading.normalize() * self.max_speed, velocity)
+        } else {
+            Vector3::zero()
+        };
+        let cohesion = self.seek(position, velocity, center / neighbors.len() as Real);
+
+        (separation, alignment, cohesion)
+    }
+}
+
+impl ForceGenerator for Flocking {
+    fn apply(&mut self, _context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        if let (Some(force), Some(body)) =
+            (self.forces.get(&body_handle), bodies.get_mut(body_handle))
+        {
+            body.add_force(force);
+        }
+    }
+
+    fn update(
+        &mut self,
+        context: &ForceContext,
+        bodies: &BodySet,
+        registered_bodies: &[BodyHandle],
+    ) {
+        self.forces.clear();
+        self.wander_offsets
+            .retain(|body_handle, _| registered_bodies.contains(body_handle));
+
+        let flock: Vec<_> = registered_bodies
+            .iter()
+            .filter_map(|&body_handle| {
+                let body = bodies.get(body_handle)?;
+                Some((body_handle, body.position, body.velocity))
+            })
+            .collect();
+
+        // Bucket the flock into cells as large as the neighbor radius,
+        // so that neighbors are always in the same or an adjacent cell.
+        // Without a neighbor radius, bodies have no neighbors to bucket.
+        let cell_size = self.neighbor_radius;
+        let cell = |position: Vector3| {
+            (
+                (position.x / cell_size).floor() as i32,
+                (position.y / cell_size).floor() as i32,
+                (position.z / cell_size).floor() as i32,
+            )
+        };
+        let mut grid: HashMap<(i32, i32, i32), Vec<usize>> = HashMap::new();
+        if self.neighbor_radius > 0.0 {
+            for (index, &(_, position, _)) in flock.iter().enumerate() {
+                grid.entry(cell(position)).or_default().push(index);
+            }
+        }
+
+        let mut neighbors = Vec::new();
+        for (index, &(body_handle, position, velocity)) in flock.iter().enumerate() {
+            neighbors.clear();
+            let (x, y, z) = cell(position);
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        // Far out or with tiny cells, the cell coordinates saturate
+                        let neighbor_cell = (
+                            x.saturating_add(dx),
+                            y.saturating_add(dy),
+                            z.saturating_add(dz),
+                        );
+                        let occupants = match grid.get(&neighbor_cell) {
+                            Some(occupants) => occupants,
+                            None => continue,
+                        };
+                        for &other in occupants {
+                            let (_, other_position, other_velocity) = flock[other];
+                            if other != index
+                                && (other_position - position).magnitude_squared()
+                                    <= self.neighbor_radius.powi(2)
+                            {
+                                neighbors.push((other_position, other_velocity));
+                            }
+                        }
+                    }
+                }
+            }
+
+            let weights = self.weights;
+            let mut force = Vector3::zero();
+            if let Some(target) = self.target {
+                force += self.seek(position, velocity, target) * weights.seek;
+                force += self.arrive(position, velocity, target) * weights.arrive;
+            }
+            if let Some(threat) = self.threat {
+                force += self.flee(position, velocity, threat) * weights.flee;
+            }
+            if weights.wander != 0.0 {
+                force +=
+                    self.wander(body_handle, position, velocity, context.duration) * weights.wander;
+            }
+            let (separation, alignment, cohesion) = self.flock(position, velocity, &neighbors);
+            force += separation * weights.separation
+                + alignment * weights.alignment
+                + cohesion * weights.cohesion;
+
+            self.forces
+                .insert(body_handle, force.clamp_magnitude(self.max_force));
+        }
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PhysicsWorld;
+
+    fn spring_world(velocity: Vector3) -> (PhysicsWorld, [BodyHandle; 2]) {
+        let mut world = PhysicsWorld::default();
+        let body = |mass, x, velocity| {
+            Body::dynamic(mass)
+                .damping(1.0)
+                .position(Vector3::new(x, 0.0, 0.0))
+                .velocity(velocity)
+                .build()
+                .unwrap()
+        };
+        let first = world.add_body(body(1.0, 0.0, velocity));
+        let second = world.add_body(body(3.0, 2.0, Vector3::new(-1.0, 0.0, 0.0)));
+        (world, [first, second])
+    }
+
+    fn momentum(world: &PhysicsWorld, handles: &[BodyHandle]) -> Vector3 {
+        handles.iter().fold(Vector3::zero(), |momentum, &handle| {
+            let body = &world.bodies[handle];
+            momentum + body.velocity * body.mass()
+        })
+    }
+
+    fn assert_momentum_conserved<G: ForceGenerator + 'static>(
+        generator: impl FnOnce(BodyHandle) -> G,
+    ) {
+        let (mut world, handles) = spring_world(Vector3::new(0.0, 1.0, 0.5));
+        let spring = world.add_force_generator(generator(handles[1]));
+        world.register_force(spring, handles[0]);
+
+        let initial = momentum(&world, &handles);
+        for _ in 0..500 {
+            world.tick(1.0 / 60.0);
+            let current = momentum(&world, &handles);
+            assert!((current - initial).magnitude() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn spring_conserves_momentum() {
+        assert_momentum_conserved(|end_body_handle| Spring {
+            end_body_handle,
+            spring_constant: 5.0,
+            rest_length: 1.0,
+        });
+    }
+
+    #[test]
+    fn damped_spring_conserves_momentum() {
+        assert_momentum_conserved(|end_body_handle| DampedSpring {
+            end_body_handle,
+            spring_constant: 5.0,
+            rest_length: 3.0,
+            damping: 0.5,
+        });
+    }
+
+    #[test]
+    fn compressed_spring_pushes_apart() {
+        let (mut world, handles) = spring_world(Vector3::zero());
+        let spring = world.add_force_generator(Spring {
+            end_body_handle: handles[1],
+            spring_constant: 1.0,
+            rest_length: 4.0,
+        });
+        world.register_force(spring, handles[0]);
+
+        let (first, second) = (world.bodies[handles[0]], world.bodies[handles[1]]);
+        world.tick(1.0 / 60.0);
+
+        assert!(world.bodies[handles[0]].velocity.x < first.velocity.x);
+        assert!(world.bodies[handles[1]].velocity.x > second.velocity.x);
+    }
+
+    #[test]
+    fn damped_spring_settles_at_rest_length() {
+        let (mut world, handles) = spring_world(Vector3::new(2.0, 0.0, 0.0));
+        let spring = world.add_force_generator(DampedSpring {
+            end_body_handle: handles[1],
+            spring_constant: 5.0,
+            rest_length: 1.5,
+            damping: 2.0,
+        });
+        world.register_force(spring, handles[0]);
+
+        for _ in 0..1000 {
+            world.tick(1.0 / 60.0);
+        }
+
+        let first = &world.bodies[handles[0]];
+        let second = &world.bodies[handles[1]];
+        assert!((first.velocity - second.velocity).magnitude() < 1e-2);
+        assert!(((first.position - second.position).magnitude() - 1.5).abs() < 1e-2);
+    }
+
+    #[test]
+    fn submerged_fraction_of_sphere() {
+        let fraction = |depth| Buoyancy::submerged_fraction(depth, 2.0);
+        assert_eq!(fraction(-3.0), 0.0);
+        assert_eq!(fraction(-2.0), 0.0);
+        assert!((fraction(0.0) - 0.5).abs() < 1e-6);
+        assert!((fraction(1.0) - 0.84375).abs() < 1e-6);
+        assert!((fraction(-1.0) - 0.15625).abs() < 1e-6);
+        assert_eq!(fraction(2.0), 1.0);
+        assert_eq!(fraction(5.0), 1.0);
+    }
+
+    #[test]
+    fn submerged_fraction_increases_with_depth() {
+        let mut previous = 0.0;
+        for step in 0..=100 {
+            let depth = -1.0 + step as Real * 0.02;
+            let fraction = Buoyancy::submerged_fraction(depth, 1.0);
+            assert!(fraction >= previous);
+            previous = fraction;
+        }
+        assert_eq!(previous, 1.0);
+    }
+
+    fn floating_depth(surface: WaterSurface, density: Real) -> Real {
+        let mut world = PhysicsWorld::default();
+        let body = world.add_body(
+            Body::dynamic(1.0)
+                .damping(1.0)
+                .shape(Shape::sphere(0.5))
+                .density(density)
+                .position(Vector3::new(0.0, 2.0, 0.0))
+                .build()
+                .unwrap(),
+        );
+        let gravity = world.add_force_generator(Gravity::default());
+        let mut buoyancy = Buoyancy::new(surface);
+        buoyancy.drag = 2000.0;
+        let surface_up = buoyancy.surface.up();
+        let buoyancy = world.add_force_generator(buoyancy);
+        world.register_force(gravity, body);
+        world.register_force(buoyancy, body);
+
+        for _ in 0..2000 {
+            world.tick(1.0 / 120.0);
+        }
+        assert!(world.bodies[body].velocity.magnitude() < 1e-2);
+        -world.bodies[body].position.dot(surface_up)
+    }
+
+    #[test]
+    fn bodies_float_at_density_ratio() {
+        // Half as dense as water floats with its center at the surface
+        assert!(floating_depth(WaterSurface::flat(0.0), 500.0).abs() < 1e-2);
+
+        // A quarter as dense floats with a quarter of its volume submerged
+        let depth = floating_depth(WaterSurface::flat(0.0), 250.0);
+        assert!((Buoyancy::submerged_fraction(depth, 0.5) - 0.25).abs() < 1e-2);
+    }
+
+    #[test]
+    fn buoyancy_pushes_along_plane_normal() {
+        let mut bodies = BodySet::new();
+        let body = bodies.insert(
+            Body::dynamic(1.0)
+                .shape(Shape::sphere(1.0))
+                .build()
+                .unwrap(),
+        );
+        let normal = Vector3::new(1.0, 1.0, 0.0).normalize();
+        let mut buoyancy = Buoyancy::new(WaterSurface::Plane {
+            point: normal * 5.0,
+            normal,
+        });
+        buoyancy.apply(&ForceContext::default(), body, &mut bodies);
+
+        let force = bodies[body].force_accumulator;
+        let volume = Shape::sphere(1.0).volume();
+        assert!((force - normal * (1000.0 * volume * buoyancy.gravity)).magnitude() < 1e-1);
+    }
+
+    #[test]
+    fn point_particles_float() {
+        let mut bodies = BodySet::new();
+        let body = bodies.insert(Body::dynamic(0.5).point_volume(2e-3).build().unwrap());
+        let buoyancy_at = |bodies: &mut BodySet, y| {
+            bodies[body].position = Vector3::new(0.0, y, 0.0);
+            bodies[body].force_accumulator = Vector3::zero();
+            Buoyancy::new(WaterSurface::flat(0.0)).apply(&ForceContext::default(), body, bodies);
+            bodies[body].force_accumulator.y
+        };
+
+        // The particle floats like a sphere of its volume
+        let buoyancy = Buoyancy::new(WaterSurface::flat(0.0));
+        let radius = bodies[body].equivalent_radius();
+        assert!((Shape::sphere(radius).volume() - 2e-3).abs() < 1e-8);
+        let full = buoyancy.liquid_density * 2e-3 * buoyancy.gravity;
+        assert_eq!(buoyancy_at(&mut bodies, 1.0), 0.0);
+        assert!((buoyancy_at(&mut bodies, 0.0) - 0.5 * full).abs() < 1e-4);
+        let partial = Buoyancy::submerged_fraction(0.5 * radius, radius) * full;
+        assert!((buoyancy_at(&mut bodies, -0.5 * radius) - partial).abs() < 1e-4);
+        assert!((buoyancy_at(&mut bodies, -1.0) - full).abs() < 1e-4);
+    }
+
+    #[test]
+    fn stiff_spring_stays_stable_at_high_stiffness() {
+        let anchor = Vector3::new(0.0, 5.0, 0.0);
+
+        // Underdamped, critically damped and overdamped
+        for &damping in [5.0, 2000.0, 1e4].iter() {
+            for &duration in [1.0 / 60.0, 0.1, 0.5].iter() {
+                let mut world = PhysicsWorld::default();
+                let spring = world.add_force_generator(StiffSpring {
+                    anchor,
+                    spring_constant: 1e6,
+                    damping,
+                });
+                let body = world.add_body(
+                    Body::dynamic(3.0)
+                        .damping(1.0)
+                        .position(anchor + Vector3::new(1.0, -2.0, 0.5))
+                        .velocity(Vector3::new(4.0, 0.0, -3.0))
+                        .build()
+                        .unwrap(),
+                );
+                world.register_force(spring, body);
+
+                for _ in 0..(20.0 / duration) as usize {
+                    world.tick(duration);
+                    let offset = world.bodies[body].position - anchor;
+                    assert!(
+                        offset.is_finite() && offset.magnitude() < 10.0,
+                        "spring with damping {} diverged at a tick of {}s",
+                        damping,
+                        duration
+                    );
+                }
+
+                let body = &world.bodies[body];
+                assert!((body.position - anchor).magnitude() < 1e-3);
+                assert!(body.velocity.magnitude() < 1e-2);
+            }
+        }
+    }
+
+    #[test]
+    fn explosion_implodes_then_blasts_then_lifts() {
+        let duration = 1.0 / 64.0;
+        let mut world = PhysicsWorld::default();
+        let mut explosion = Explosion::new(Vector3::zero());
+        explosion.implosion_duration = 0.125;
+        let explosion = world.add_force_generator(explosion);
+
+        // Heavy bodies barely move, so each stays in place for every phase
+        let body = |position| {
+            Body::dynamic(100.0)
+                .damping(1.0)
+                .position(position)
+                .build()
+                .unwrap()
+        };
+        let beside = world.add_body(body(Vector3::new(5.0, 0.0, 0.0)));
+        let above = world.add_body(body(Vector3::new(0.0, 5.0, 0.0)));
+        world.register_force(explosion, beside);
+        world.register_force(explosion, above);
+
+        let mut was_pulled_in = false;
+        let mut was_pushed_out = false;
+        let mut was_lifted = false;
+        let mut finished_at = None;
+        for tick in 0..400 {
+            let time = tick as Real * duration;
+            let velocities = (world.bodies[beside].velocity, world.bodies[above].velocity);
+            world.tick(duration);
+            let pull = world.bodies[beside].velocity.x - velocities.0.x;
+            let lift = world.bodies[above].velocity.y - velocities.1.y;
+
+            if time <= 0.125 {
+                // Every tick of the implosion, starting with the first, pulls bodies in
+                assert!(pull < 0.0 && lift < 0.0, "no implosion at {}", time);
+                was_pulled_in = true;
+            } else if time < 0.225 {
+                // The shell hasn't reached the bodies 5 away yet
+                assert_eq!(pull, 0.0, "early shockwave at {}", time);
+            } else if time < 0.3 {
+                was_pushed_out |= pull > 0.0;
+            } else if time > 1.125 && time < 3.125 {
+                // Only the chimney is left, which lifts the body above the detonation
+                assert_eq!(pull, 0.0);
+                assert!(lift > 0.0, "no convection at {}", time);
+                was_lifted = true;
+            }
+
+            let generator = world.force_generators[explosion]
+                .downcast_ref::<Explosion>()
+                .unwrap();
+            if generator.is_finished() && finished_at.is_none() {
+                finished_at = Some(time);
+            }
+        }
+
+        assert!(was_pulled_in && was_pushed_out && was_lifted);
+
+        // The explosion ends once a tick starts after the implosion and the longest phase
+        assert_eq!(finished_at, Some(3.125 + duration));
+    }
+
+    fn context(duration: Real, elapsed: Real) -> ForceContext {
+        ForceContext {
+            duration,
+            elapsed,
+            tick: 0,
+            boundary: None,
+        }
+    }
+
+    /// The force a generator applies to the body over one tick.
+    fn applied_force(generator: &mut dyn ForceGenerator, body: Body, elapsed: Real) -> Vector3 {
+        let mut bodies = BodySet::new();
+        let body_handle = bodies.insert(body);
+        generator.apply(&context(1.0 / 60.0, elapsed), body_handle, &mut bodies);
+        bodies[body_handle].force_accumulator
+    }
+
+    fn kite(velocity: Vector3) -> Body {
+        Body::dynamic(1.0)
+            .shape(Shape::sphere(0.5))
+            .position(Vector3::new(3.0, 20.0, -4.0))
+            .velocity(velocity)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn wind_pushes_bodies_downwind() {
+        let velocity = Vector3::new(8.0, 0.0, 6.0);
+        let mut wind = Wind::new(velocity, Drag::air());
+        wind.turbulence_strength = 0.0;
+
+        // Gusts change the wind speed but not its direction
+        for &time in [0.0, 1.3, 7.9].iter() {
+            let force = applied_force(&mut wind, kite(Vector3::zero()), time);
+            assert!(force.magnitude() > 0.0);
+            assert!((force.normalize() - velocity.normalize()).magnitude() < 1e-5);
+        }
+
+        // A body carried along with a steady wind feels nothing
+        wind.gust_strength = 0.0;
+        assert_eq!(
+            applied_force(&mut wind, kite(velocity), 2.0),
+            Vector3::zero()
+        );
+    }
+
+    #[test]
+    fn wind_samples_are_repeatable() {
+        let seeded_wind = |seed| {
+            let mut wind = Wind::new(Vector3::new(5.0, 0.0, 0.0), Drag::air());
+            wind.seed = seed;
+            wind
+        };
+        let position = Vector3::new(12.0, 3.0, -7.0);
+
+        let mut wind = seeded_wind(42);
+        let sample = wind.sample(position, 4.5);
+        assert_eq!(sample, wind.sample(position, 4.5));
+        assert_eq!(sample, seeded_wind(42).sample(position, 4.5));
+
+        wind.seed = 43;
+        assert_ne!(sample, wind.sample(position, 4.5));
+    }
+
+    /// The forces between two charges the given distance apart on the x axis.
+    fn coulomb_forces(charge: Real, other_charge: Real, distance: Real) -> (Vector3, Vector3) {
+        let mut bodies = BodySet::new();
+        let charged = |x, charge| {
+            Body::dynamic(1.0)
+                .position(Vector3::new(x, 0.0, 0.0))
+                .charge(charge)
+                .build()
+                .unwrap()
+        };
+        let handles = [
+            bodies.insert(charged(distance, charge)),
+            bodies.insert(charged(0.0, other_charge)),
+        ];
+
+        let mut coulomb = Coulomb {
+            coulomb_constant: 1.0,
+            ..Coulomb::default()
+        };
+        let context = context(1.0 / 60.0, 0.0);
+        coulomb.update(&context, &bodies, &handles);
+        for &body_handle in handles.iter() {
+            coulomb.apply(&context, body_handle, &mut bodies);
+        }
+        (
+            bodies[handles[0]].force_accumulator,
+            bodies[handles[1]].force_accumulator,
+        )
+    }
+
+    #[test]
+    fn like_charges_repel_and_opposite_charges_attract() {
+        // Coulomb's law: k * q1 * q2 / r^2 = 2 * 3 / 2^2
+        let (force, other_force) = coulomb_forces(2.0, 3.0, 2.0);
+        assert!((force - Vector3::new(1.5, 0.0, 0.0)).magnitude() < 1e-6);
+        assert_eq!(other_force, force.inverse());
+
+        let (force, other_force) = coulomb_forces(-2.0, 3.0, 2.0);
+        assert!((force - Vector3::new(-1.5, 0.0, 0.0)).magnitude() < 1e-6);
+        assert_eq!(other_force, force.inverse());
+
+        // Doubling the distance quarters the force
+        let (force, _) = coulomb_forces(2.0, 3.0, 4.0);
+        assert!((force - Vector3::new(1.5 / 4.0, 0.0, 0.0)).magnitude() < 1e-6);
+    }
+
+    #[test]
+    fn coulomb_conserves_momentum() {
+        let mut world = PhysicsWorld::default();
+        let mut coulomb = Coulomb::new(0.1);
+        coulomb.coulomb_constant = 1.0;
+        let coulomb = world.add_force_generator(coulomb);
+        let handles = [(1.0, -0.5, 0.0), (2.0, 1.5, 0.3), (-3.0, 0.0, 2.0)]
+            .iter()
+            .map(|&(charge, x, y): &(Real, Real, Real)| {
+                let body = world.add_body(
+                    Body::dynamic(1.0 + charge.abs())
+                        .damping(1.0)
+                        .position(Vector3::new(x, y, 0.0))
+                        .charge(charge)
+                        .build()
+                        .unwrap(),
+                );
+                world.register_force(coulomb, body);
+                body
+            })
+            .collect::<Vec<_>>();
+
+        for _ in 0..500 {
+            world.tick(1.0 / 120.0);
+            assert!(momentum(&world, &handles).magnitude() < 1e-3);
+        }
+
+        // The bodies have been moving, so the forces on them weren't all zero
+        assert!(handles
+            .iter()
+            .all(|&handle| world.bodies[handle].velocity.magnitude() > 0.1));
+    }
+
+    #[test]
+    fn uniform_magnetic_field_bends_charges_into_circles() {
+        let (mass, charge, speed, field) = (0.5, -2.0, 3.0, 1.5);
+        let mut world = PhysicsWorld::default();
+        let lorentz = world.add_force_generator(Lorentz::new(
+            Field::default(),
+            Field::Uniform(Vector3::new(0.0, 0.0, field)),
+        ));
+        let body = world.add_body(
+            Body::dynamic(mass)
+                .damping(1.0)
+                .velocity(Vector3::new(speed, 0.0, 0.0))
+                .charge(charge)
+                .build()
+                .unwrap(),
+        );
+        world.register_force(lorentz, body);
+
+        // A negative charge moving along x through a field along z curves towards +y
+        let radius = mass * speed / (charge.abs() * field);
+        let center = Vector3::new(0.0, radius, 0.0);
+        let period = 2.0 * std::f32::consts::PI * radius / speed;
+        let duration = 1e-4;
+        let mut furthest: Real = 0.0;
+        for _ in 0..(period / duration) as usize {
+            world.tick(duration);
+            let body = &world.bodies[body];
+            assert!(((body.position - center).magnitude() - radius).abs() < 0.01 * radius);
+            assert!((body.velocity.magnitude() - speed).abs() < 0.01 * speed);
+            furthest = furthest.max(body.position.y);
+        }
+
+        // The body goes all the way around and comes back
+        assert!((furthest - 2.0 * radius).abs() < 0.01 * radius);
+        assert!(world.bodies[body].position.magnitude() < 0.01 * radius);
+    }
+
+    #[test]
+    fn electric_field_pushes_along_itself() {
+        let lorentz = Lorentz::new(
+            Field::Function(Box::new(|position, time| position * time)),
+            Field::default(),
+        );
+        let position = Vector3::new(1.0, -2.0, 0.5);
+
+        // The force is the same whatever the velocity, and flips with the charge
+        let force = lorentz.force(3.0, position, Vector3::zero(), 2.0);
+        assert_eq!(force, position * 6.0);
+        assert_eq!(lorentz.force(3.0, position, Vector3::x(), 2.0), force);
+        assert_eq!(
+            lorentz.force(-3.0, position, Vector3::zero(), 2.0),
+            force.inverse()
+        );
+    }
+
+    #[test]
+    fn drag_needs_motion_through_the_air() {
+        let drag = Drag::air();
+        let body = Body::dynamic(1.0).build().unwrap();
+        assert_eq!(
+            drag.force(&body, Vector3::zero(), 1.0 / 60.0),
+            Vector3::zero()
+        );
+
+        // Point particles have the frontal area of their point volume unless given one
+        let velocity = Vector3::new(0.0, -10.0, 0.0);
+        let force = drag.force(&body, velocity, 1.0 / 60.0);
+        assert!(force.y > 0.0 && force.x == 0.0 && force.z == 0.0);
+
+        let expected = 0.5 * Drag::SEA_LEVEL_AIR_DENSITY * 100.0 * 0.47 * 0.2;
+        let body = Body::dynamic(1.0)
+            .cross_sectional_area(0.2)
+            .build()
+            .unwrap();
+        let force = drag.force(&body, velocity, 1.0 / 60.0);
+        assert!((force.y - expected).abs() < 1e-4);
+    }
+
+    #[test]
+    fn drag_weakens_with_altitude() {
+        let drag = Drag::air();
+        assert_eq!(drag.air_density_at(0.0), Drag::SEA_LEVEL_AIR_DENSITY);
+        let thin = drag.air_density_at(Drag::EARTH_SCALE_HEIGHT);
+        assert!((thin - Drag::SEA_LEVEL_AIR_DENSITY / std::f32::consts::E).abs() < 1e-5);
+
+        let drag_at = |altitude| {
+            let body = Body::dynamic(1.0)
+                .shape(Shape::sphere(0.5))
+                .position(Vector3::new(0.0, altitude, 0.0))
+                .build()
+                .unwrap();
+            drag.force(&body, Vector3::x(), 1.0 / 60.0).magnitude()
+        };
+        assert!(drag_at(1000.0) < drag_at(0.0));
+        assert!(drag_at(10_000.0) < drag_at(1000.0));
+    }
+
+    #[test]
+    fn drag_never_reverses_velocity() {
+        let mut world = PhysicsWorld::default();
+        let mut drag = Drag::new(50.0, 5.0);
+        drag.air_density = Drag::SEA_LEVEL_AIR_DENSITY;
+        let drag = world.add_force_generator(drag);
+        let velocity = Vector3::new(30.0, 0.0, -40.0);
+        let body = world.add_body(
+            Body::dynamic(0.1)
+                .damping(1.0)
+                .shape(Shape::sphere(1.0))
+                .velocity(velocity)
+                .build()
+                .unwrap(),
+        );
+        world.register_force(drag, body);
+
+        // One long tick at most brings the body to a stop
+        world.tick(5.0);
+        let after = world.bodies[body].velocity;
+        assert!(after.dot(velocity) >= 0.0);
+        assert!(after.magnitude() < 1e-3);
+    }
+
+    /// A flock using only the given behaviors, with bodies at the given positions.
+    fn flock_world(flocking: Flocking, positions: &[Vector3]) -> (PhysicsWorld, Vec<BodyHandle>) {
+        let mut world = PhysicsWorld::default();
+        let flocking = world.add_force_generator(flocking);
+        let handles = positions
+            .iter()
+            .map(|&position| {
+                let body = world.add_body(
+                    Body::dynamic(1.0)
+                        .damping(1.0)
+                        .position(position)
+                        .build()
+                        .unwrap(),
+                );
+                world.register_force(flocking, body);
+                body
+            })
+            .collect();
+        (world, handles)
+    }
+
+    fn only(weights: impl FnOnce(&mut SteeringWeights)) -> SteeringWeights {
+        let mut only = SteeringWeights {
+            seek: 0.0,
+            flee: 0.0,
+            arrive: 0.0,
+            wander: 0.0,
+            separation: 0.0,
+            alignment: 0.0,
+            cohesion: 0.0,
+        };
+        weights(&mut only);
+        only
+    }
+
+    #[test]
+    fn separation_pushes_close_boids_apart() {
+        let mut flocking = Flocking::new(2.0, 5.0);
+        flocking.weights = only(|weights| weights.separation = 1.0);
+        let (mut world, handles) = flock_world(
+            flocking,
+            &[Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.5, 0.0, 0.0)],
+        );
+
+        for _ in 0..120 {
+            world.tick(1.0 / 60.0);
+        }
+
+        // Once outside each other's separation radius they stop pushing, and drift apart
+        let first = &world.bodies[handles[0]];
+        let second = &world.bodies[handles[1]];
+        assert!(first.velocity.x < 0.0 && second.velocity.x > 0.0);
+        assert!((second.position - first.position).magnitude() > 1.5);
+    }
+
+    #[test]
+    fn arrive_settles_on_the_target() {
+        let target = Vector3::new(10.0, 0.0, -5.0);
+        let mut flocking = Flocking::new(4.0, 8.0);
+        flocking.weights = only(|weights| weights.arrive = 1.0);
+        flocking.target = Some(target);
+        let (mut world, handles) = flock_world(flocking, &[Vector3::zero()]);
+
+        let mut fastest: Real = 0.0;
+        for _ in 0..1200 {
+            world.tick(1.0 / 60.0);
+            fastest = fastest.max(world.bodies[handles[0]].velocity.magnitude());
+        }
+
+        let body = &world.bodies[handles[0]];
+        assert!(fastest <= 4.0 + 0.2);
+        assert!(
+            (body.position - target).magnitude() < 0.05,
+            "{:?}",
+            body.position
+        );
+        assert!(body.velocity.magnitude() < 0.05);
+    }
+
+    #[test]
+    fn flocks_without_neighbors_steer_far_from_the_origin() {
+        let target = Vector3::new(300.0, 0.0, 0.0);
+        let mut flocking = Flocking::new(4.0, 8.0);
+        flocking.weights = only(|weights| weights.seek = 1.0);
+        flocking.neighbor_radius = 0.0;
+        flocking.target = Some(target);
+        let (mut world, handles) = flock_world(
+            flocking,
+            &[
+                Vector3::new(300.0, 0.0, 10.0),
+                Vector3::new(300.0, 0.0, -10.0),
+            ],
+        );
+
+        world.tick(1.0 / 60.0);
+        assert!(world.bodies[handles[0]].velocity.z < 0.0);
+        assert!(world.bodies[handles[1]].velocity.z > 0.0);
+    }
 }
@@ -0,0 +1,549 @@
+use crate::{BodyHandle, BodySet, ForceContext, ForceGenerator, Real, Vector3};
+use std::collections::BTreeMap;
+
+/// Newton's gravitational constant in cubic meters per kilogram per second squared.
+pub const GRAVITATIONAL_CONSTANT: Real = 6.674e-11;
+
+/// The acceleration towards a mass at the given offset, with Plummer softening
+/// to keep it finite when the offset is close to zero.
+fn attraction(
+    offset: Vector3,
+    mass: Real,
+    gravitational_constant: Real,
+    softening: Real,
+) -> Vector3 {
+    let distance_squared = offset.magnitude_squared() + softening.powi(2);
+    if distance_squared <= 0.0 {
+        return Vector3::zero();
+    }
+    offset * (gravitational_constant * mass / (distance_squared * distance_squared.sqrt()))
+}
+
+/// Pulls bodies towards a single fixed mass, such as a planet or a black hole.
+///
+/// Unlike `Gravity`, the pull points at the center and weakens with the square of the distance.
+pub struct PointGravity {
+    pub center: Vector3,
+    pub mass: Real,
+    pub gravitational_constant: Real,
+
+    /// Smooths out the pull near the center, where it would otherwise grow without bound
+    pub softening: Real,
+}
+
+impl PointGravity {
+    pub fn new(center: Vector3, mass: Real) -> Self {
+        Self {
+            center,
+            mass,
+            gravitational_constant: GRAVITATIONAL_CONSTANT,
+            softening: 0.0,
+        }
+    }
+
+    /// The acceleration of a body at the given position.
+    pub fn acceleration(&self, position: Vector3) -> Vector3 {
+        attraction(
+            self.center - position,
+            self.mass,
+            self.gravitational_constant,
+            self.softening,
+        )
+    }
+}
+
+impl ForceGenerator for PointGravity {
+    fn apply(&mut self, _context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        let body = match bodies.get_mut(body_handle) {
+            Some(body) => body,
+            None => return,
+        };
+
+        if body.has_infinite_mass() {
+            return;
+        }
+
+        let force = self.acceleration(body.position) * (body.mass() * body.gravity_scale);
+        body.add_force(&force);
+    }
+}
+
+/// How `NBodyGravity` sums up the attraction between bodies.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum NBodyMethod {
+    /// Sums the attraction between every pair of bodies exactly, taking O(n²) time.
+    BruteForce,
+
+    /// Approximates distant groups of bodies by their center of mass using
+    /// an octree, taking O(n log n) time.
+    ///
+    /// A group is approximated when its size divided by its distance is less than
+    /// the opening angle, so an angle of zero gives the same result as brute force.
+    BarnesHut { opening_angle: Real },
+}
+
+/// Makes every body the generator is registered for attract every other one.
+///
+/// Bodies with infinite mass are neither attracted nor attract others.
+pub struct NBodyGravity {
+    pub method: NBodyMethod,
+    pub gravitational_constant: Real,
+
+    /// Smooths out the pull between bodies that pass close to each other
+    pub softening: Real,
+
+    accelerations: BTreeMap<BodyHandle, Vector3>,
+}
+
+impl NBodyGravity {
+    pub fn new(method: NBodyMethod) -> Self {
+        Self {
+            method,
+            gravitational_constant: GRAVITATIONAL_CONSTANT,
+            softening: 0.0,
+            accelerations: BTreeMap::new(),
+        }
+    }
+
+    /// Uses Barnes-Hut with a commonly used opening angle.
+    pub fn barnes_hut() -> Self {
+        Self::new(NBodyMethod::BarnesHut { opening_angle: 0.5 })
+    }
+
+    pub fn brute_force() -> Self {
+        Self::new(NBodyMethod::BruteForce)
+    }
+
+    /// The acceleration of a body as of the last update.
+    pub fn acceleration(&self, body_handle: BodyHandle) -> Option<Vector3> {
+        self.accelerations.get(&body_handle).copied()
+    }
+
+    fn brute_force_accelerations(&self, masses: &[(Vector3, Real)]) -> Vec<Vector3> {
+        let mut accelerations = vec![Vector3::zero(); masses.len()];
+        for (i, &(position, mass)) in masses.iter().enumerate() {
+            for (j, &(other_position, other_mass)) in masses.iter().enumerate().skip(i + 1) {
+                // The pull per unit of mass, shared by both bodies
+                let pull = attraction(
+                    other_position - position,
+                    1.0,
+                    self.gravitational_constant,
+                    self.softening,
+                );
+                accelerations[i] += pull * other_mass;
+                accelerations[j] -= pull * mass;
+            }
+        }
+        accelerations
+    }
+}
+
+impl ForceGenerator for NBodyGravity {
+    fn apply(&mut self, _context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        let acceleration = match self.accelerations.get(&body_handle) {
+            Some(acceleration) => *acceleration,
+            None => return,
+        };
+
+        if let Some(body) = bodies.get_mut(body_handle) {
+            let force = acceleration * (body.mass() * body.gravity_scale);
+            body.add_force(&force);
+        }
+    }
+
+    fn update(
+        &mut self,
+        _context: &ForceContext,
+        bodies: &BodySet,
+        registered_bodies: &[BodyHandle],
+    ) {
+        self.accelerations.clear();
+
+        let mut handles = Vec::with_capacity(registered_bodies.len());
+        let mut masses = Vec::with_capacity(registered_bodies.len());
+        for &body_handle in registered_bodies {
+            if let Some(body) = bodies.get(body_handle) {
+                if !body.has_infinite_mass() {
+                    handles.push(body_handle);
+                    masses.push((body.position, body.mass()));
+                }
+            }
+        }
+
+        let accelerations = match self.method {
+            NBodyMethod::BruteForce => self.brute_force_accelerations(&masses),
+            NBodyMethod::BarnesHut { opening_angle } => {
+                let octree = Octree::new(&masses);
+                (0..masses.len())
+                    .map(|i| {
+                        octree.acceleration(
+                            i,
+                            &masses,
+                            opening_angle,
+                            self.gravitational_constant,
+                            self.softening,
+                        )
+                    })
+                    .collect()
+            }
+        };
+
+        self.accelerations = handles.into_iter().zip(accelerations).collect();
+    }
+}
+
+/// Octree nodes deeper than this keep all of their bodies instead of
+/// subdividing, so that bodies at the same position can't recurse forever.
+const MAX_OCTREE_DEPTH: usize = 32;
+
+struct OctreeNode {
+    center: Vector3,
+    half_size: Real,
+    mass: Real,
+
+    /// The sum of the positions of the bodies in the node weighted by their masses
+    weighted_position: Vector3,
+
+    /// The index of the first of the node's eight children, which are stored together
+    children: Option<usize>,
+
+    /// The bodies in the node if it's a leaf
+    bodies: Vec<usize>,
+}
+
+impl OctreeNode {
+    fn new(center: Vector3, half_size: Real) -> Self {
+        Self {
+            center,
+            half_size,
+            mass: 0.0,
+            weighted_position: Vector3::zero(),
+            children: None,
+            bodies: Vec::new(),
+        }
+    }
+
+    /// The index among the node's children of the octant containing the position.
+    fn octant(&self, position: Vector3) -> usize {
+        (position.x >= self.center.x) as usize
+            | ((position.y >= self.center.y) as usize) << 1
+            | ((position.z >= self.center.z) as usize) << 2
+    }
+
+    fn contains(&self, position: Vector3) -> bool {
+        (position.x - self.center.x).abs() <= self.half_size
+            && (position.y - self.center.y).abs() <= self.half_size
+            && (position.z - self.center.z).abs() <= self.half_size
+    }
+}
+
+/// Groups bodies spatially so that distant groups can be treated as a single mass.
+struct Octree {
+    nodes: Vec<OctreeNode>,
+}
+
+impl Octree {
+    fn new(masses: &[(Vector3, Real)]) -> Self {
+        let mut octree = Self { nodes: Vec::new() };
+        if masses.is_empty() {
+            return octree;
+        }
+
+        let (min, max) =
+            masses
+                .iter()
+                .fold((masses[0].0, masses[0].0), |(min, max), &(position, _)| {
+                    (
+                        Vector3::new(
+                            min.x.min(position.x),
+                            min.y.min(position.y),
+                            min.z.min(position.z),
+                        ),
+                        Vector3::new(
+                            max.x.max(position.x),
+                            max.y.max(position.y),
+                            max.z.max(position.z),
+                        ),
+                    )
+                });
+        let extents = max - min;
+        let half_size = 0.5 * extents.x.max(extents.y).max(extents.z) + Real::EPSILON;
+        octree
+            .nodes
+            .push(OctreeNode::new((min + max) * 0.5, half_size));
+
+        for index in 0..masses.len() {
+            octree.insert(0, index, masses, 0);
+        }
+        octree
+    }
+
+    fn insert(
+        &mut self,
+        node_index: usize,
+        index: usize,
+        masses: &[(Vector3, Real)],
+        depth: usize,
+    ) {
+        let (position, mass) = masses[index];
+        let node = &mut self.nodes[node_index];
+        node.mass += mass;
+        node.weighted_position += position * mass;
+
+        if let Some(first_child) = node.children {
+            let child = first_child + node.octant(position);
+            self.insert(child, index, masses, depth + 1);
+            return;
+        }
+
+        node.bodies.push(index);
+        if node.bodies.len() == 1 || depth >= MAX_OCTREE_DEPTH {
+            return;
+        }
+
+        // The leaf now holds two bodies, so split it and push both down a level
+        let (center, half_size) = (node.center, node.half_size * 0.5);
+        let bodies = std::mem::take(&mut node.bodies);
+        let first_child = self.nodes.len();
+        self.nodes[node_index].children = Some(first_child);
+
+        for octant in 0..8 {
+            let offset = Vector3::new(
+                if octant & 1 != 0 {
+                    half_size
+                } else {
+                    -half_size
+                },
+                if octant & 2 != 0 {
+                    half_size
+                } else {
+                    -half_size
+                },
+                if octant & 4 != 0 {
+                    half_size
+                } else {
+                    -half_size
+                },
+            );
+            self.nodes.push(OctreeNode::new(center + offset, half_size));
+        }
+
+        for body in bodies {
+            let child = first_child + self.nodes[node_index].octant(masses[body].0);
+            self.insert(child, body, masses, depth + 1);
+        }
+    }
+
+    /// The acceleration of the body with the given index due to every other body.
+    fn acceleration(
+        &self,
+        index: usize,
+        masses: &[(Vector3, Real)],
+        opening_angle: Real,
+        gravitational_constant: Real,
+        softening: Real,
+    ) -> Vector3 {
+        let position = masses[index].0;
+        let mut acceleration = Vector3::zero();
+        let mut stack = Vec::new();
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if node.mass <= 0.0 {
+                continue;
+            }
+
+            match node.children {
+                None => {
+                    for &other in node.bodies.iter().filter(|&&other| other != index) {
+                        let (other_position, other_mass) = masses[other];
+                        acceleration += attraction(
+                            other_position - position,
+                            other_mass,
+                            gravitational_constant,
+                            softening,
+                        );
+                    }
+                }
+                Some(first_child) => {
+                    let center_of_mass = node.weighted_position / node.mass;
+                    let distance = (center_of_mass - position).magnitude();
+                    let far_enough = 2.0 * node.half_size < opening_angle * distance;
+
+                    // A node containing the body can't be approximated, as it would pull on itself
+                    if far_enough && !node.contains(position) {
+                        acceleration += attraction(
+                            center_of_mass - position,
+                            node.mass,
+                            gravitational_constant,
+                            softening,
+                        );
+                    } else {
+                        stack.extend(first_child..first_child + 8);
+                    }
+                }
+            }
+        }
+
+        acceleration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{noise::Random, Body, PhysicsWorld};
+    use std::f32::consts::PI;
+
+    fn body(position: Vector3, velocity: Vector3, mass: Real) -> Body {
+        Body::dynamic(mass)
+            .damping(1.0)
+            .position(position)
+            .velocity(velocity)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn point_gravity_keeps_circular_kepler_orbit() {
+        let mut world = PhysicsWorld::default();
+        let mut gravity = PointGravity::new(Vector3::zero(), 1.0);
+        gravity.gravitational_constant = 1.0;
+        let gravity = world.add_force_generator(gravity);
+
+        // Orbital speed for a circular orbit of radius one is sqrt(GM / r)
+        let start = Vector3::new(1.0, 0.0, 0.0);
+        let satellite = world.add_body(body(start, Vector3::new(0.0, 0.0, 1.0), 1.0));
+        world.register_force(gravity, satellite);
+
+        let duration = 2.5e-4;
+        let period = 2.0 * PI;
+        let ticks = (period / duration).round() as usize;
+        for _ in 0..ticks {
+            world.tick(duration);
+            let radius = world.bodies[satellite].position.magnitude();
+            assert!((radius - 1.0).abs() < 0.02);
+        }
+
+        assert!((world.bodies[satellite].position - start).magnitude() < 0.05);
+    }
+
+    #[test]
+    fn binary_stars_follow_kepler_period() {
+        for method in [
+            NBodyMethod::BruteForce,
+            NBodyMethod::BarnesHut { opening_angle: 0.5 },
+        ] {
+            let mut world = PhysicsWorld::default();
+            let mut gravity = NBodyGravity::new(method);
+            gravity.gravitational_constant = 1.0;
+            let gravity = world.add_force_generator(gravity);
+
+            // Two equal masses a unit apart each orbit their shared center
+            // at half the separation, with a period of 2π sqrt(d³ / G(m₁ + m₂))
+            let speed = 0.5_f32.sqrt();
+            let stars = [
+                world.add_body(body(
+                    Vector3::new(0.5, 0.0, 0.0),
+                    Vector3::new(0.0, speed, 0.0),
+                    1.0,
+                )),
+                world.add_body(body(
+                    Vector3::new(-0.5, 0.0, 0.0),
+                    Vector3::new(0.0, -speed, 0.0),
+                    1.0,
+                )),
+            ];
+            for &star in &stars {
+                world.register_force(gravity, star);
+            }
+
+            let duration = 1e-3;
+            let period = 2.0 * PI / 2.0_f32.sqrt();
+            for _ in 0..(period / duration).round() as usize {
+                world.tick(duration);
+                let separation =
+                    (world.bodies[stars[0]].position - world.bodies[stars[1]].position).magnitude();
+                assert!((separation - 1.0).abs() < 0.02);
+            }
+
+            let position = world.bodies[stars[0]].position;
+            assert!((position - Vector3::new(0.5, 0.0, 0.0)).magnitude() < 0.05);
+        }
+    }
+
+    fn cluster(count: usize) -> (BodySet, Vec<BodyHandle>) {
+        let mut random = Random::new(0x9e37_79b9);
+        let mut random = move || random.signed();
+
+        let mut bodies = BodySet::new();
+        let handles = (0..count)
+            .map(|_| {
+                let position = Vector3::new(random(), random(), random()) * 10.0;
+                bodies.insert(body(position, Vector3::zero(), 1.5 + random()))
+            })
+            .collect();
+        (bodies, handles)
+    }
+
+    fn accelerations(
+        method: NBodyMethod,
+        bodies: &BodySet,
+        handles: &[BodyHandle],
+    ) -> Vec<Vector3> {
+        let mut gravity = NBodyGravity::new(method);
+        gravity.gravitational_constant = 1.0;
+        gravity.softening = 0.01;
+        gravity.update(&ForceContext::default(), bodies, handles);
+        handles
+            .iter()
+            .map(|&handle| gravity.acceleration(handle).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn barnes_hut_approximates_brute_force() {
+        let (bodies, handles) = cluster(300);
+        let exact = accelerations(NBodyMethod::BruteForce, &bodies, &handles);
+
+        let opened = accelerations(
+            NBodyMethod::BarnesHut { opening_angle: 0.0 },
+            &bodies,
+            &handles,
+        );
+        for (exact, opened) in exact.iter().zip(&opened) {
+            assert!((*exact - *opened).magnitude() <= 1e-4 * exact.magnitude().max(1.0));
+        }
+
+        let approximate = accelerations(
+            NBodyMethod::BarnesHut { opening_angle: 0.5 },
+            &bodies,
+            &handles,
+        );
+        let error: Real = exact
+            .iter()
+            .zip(&approximate)
+            .map(|(exact, approximate)| (*exact - *approximate).magnitude() / exact.magnitude())
+            .sum::<Real>()
+            / handles.len() as Real;
+        assert!(error < 0.01);
+    }
+
+    #[test]
+    fn coincident_bodies_do_not_break_octree() {
+        let mut bodies = BodySet::new();
+        let handles: Vec<_> = (0..4)
+            .map(|_| bodies.insert(body(Vector3::new(1.0, 2.0, 3.0), Vector3::zero(), 1.0)))
+            .collect();
+
+        let mut gravity = NBodyGravity::barnes_hut();
+        gravity.softening = 0.1;
+        gravity.update(&ForceContext::default(), &bodies, &handles);
+        for handle in handles {
+            assert_eq!(gravity.acceleration(handle), Some(Vector3::zero()));
+        }
+    }
+}
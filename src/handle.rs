@@ -0,0 +1,159 @@
+use generational_arena::Index;
+use std::{marker::PhantomData, ops};
+
+mod private {
+    use generational_arena::Index;
+
+    /// Converts between handles and the arena indices they wrap.
+    ///
+    /// This lives in a private module so that only the handles
+    /// declared here can be used to access an `Arena`.
+    pub trait Handle: Copy {
+        fn from_index(index: Index) -> Self;
+        fn index(self) -> Index;
+    }
+}
+
+use private::Handle;
+
+macro_rules! define_handle {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(Index);
+
+        impl Handle for $name {
+            fn from_index(index: Index) -> Self {
+                Self(index)
+            }
+
+            fn index(self) -> Index {
+                self.0
+            }
+        }
+    };
+}
+
+define_handle!(
+    /// Identifies a body in a `BodySet`.
+    BodyHandle
+);
+
+define_handle!(
+    /// Identifies a force generator in a `ForceGeneratorSet`.
+    ForceGeneratorHandle
+);
+
+define_handle!(
+    /// Identifies a contact generator in a `ContactGeneratorSet`.
+    ContactGeneratorHandle
+);
+
+define_handle!(
+    /// Identifies a link between two bodies, such as a cable, in a `LinkSet`.
+    LinkHandle
+);
+
+define_handle!(
+    /// Identifies a sensor in a `SensorSet`.
+    SensorHandle
+);
+
+define_handle!(
+    /// Identifies a force field in a `ForceFieldSet`.
+    ForceFieldHandle
+);
+
+/// A generational arena whose entries can only be
+/// looked up by the handle type it was declared with.
+pub struct Arena<H, T> {
+    entries: generational_arena::Arena<T>,
+    handle: PhantomData<fn() -> H>,
+}
+
+impl<H, T> Default for Arena<H, T> {
+    fn default() -> Self {
+        Self {
+            entries: generational_arena::Arena::new(),
+            handle: PhantomData,
+        }
+    }
+}
+
+impl<H, T> Arena<H, T>
+where
+    H: Handle,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, value: T) -> H {
+        H::from_index(self.entries.insert(value))
+    }
+
+    pub fn remove(&mut self, handle: H) -> Option<T> {
+        self.entries.remove(handle.index())
+    }
+
+    pub fn contains(&self, handle: H) -> bool {
+        self.entries.contains(handle.index())
+    }
+
+    pub fn get(&self, handle: H) -> Option<&T> {
+        self.entries.get(handle.index())
+    }
+
+    pub fn get_mut(&mut self, handle: H) -> Option<&mut T> {
+        self.entries.get_mut(handle.index())
+    }
+
+    /// Borrows two distinct entries mutably at the same time.
+    pub fn get2_mut(&mut self, first: H, second: H) -> (Option<&mut T>, Option<&mut T>) {
+        self.entries.get2_mut(first.index(), second.index())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (H, &T)> {
+        self.entries
+            .iter()
+            .map(|(index, value)| (H::from_index(index), value))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (H, &mut T)> {
+        self.entries
+            .iter_mut()
+            .map(|(index, value)| (H::from_index(index), value))
+    }
+}
+
+impl<H, T> ops::Index<H> for Arena<H, T>
+where
+    H: Handle,
+{
+    type Output = T;
+
+    fn index(&self, handle: H) -> &T {
+        &self.entries[handle.index()]
+    }
+}
+
+impl<H, T> ops::IndexMut<H> for Arena<H, T>
+where
+    H: Handle,
+{
+    fn index_mut(&mut self, handle: H) -> &mut T {
+        &mut self.entries[handle.index()]
+    }
+}
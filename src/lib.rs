@@ -1,14 +1,39 @@
-pub use self::{body::*, contact::*, force::*, link::*, vector::*, world::*};
+pub use self::{
+    aabb::*, aero::*, body::*, broadphase::*, collision::*, contact::*, control::*, event::*,
+    field::*, force::*, gravity::*, handle::*, link::*, matrix::*, molecular::*, periodic::*,
+    quaternion::*, query::*, sensor::*, shape::*, vector::*, volume::*, water::*, world::*,
+};
 
+mod aabb;
+mod aero;
 mod body;
+mod broadphase;
+mod collision;
 mod contact;
+mod control;
+mod event;
+mod field;
 mod force;
+mod gravity;
+mod handle;
 mod link;
+mod matrix;
+mod molecular;
+mod noise;
+mod periodic;
+mod quaternion;
+mod query;
+mod sensor;
+mod shape;
 mod vector;
+mod volume;
+mod water;
 mod world;
 
 pub type Real = f32;
-pub type Arena<T> = generational_arena::Arena<T>;
-pub type Handle = generational_arena::Index;
-pub type BodySet = Arena<Body>;
-pub type ForceGeneratorSet = Arena<Box<dyn ForceGenerator>>;
+pub type BodySet = Arena<BodyHandle, Body>;
+pub type ForceGeneratorSet = Arena<ForceGeneratorHandle, Box<dyn ForceGenerator>>;
+pub type ContactGeneratorSet = Arena<ContactGeneratorHandle, Box<dyn ContactGenerator>>;
+pub type LinkSet = Arena<LinkHandle, Cable>;
+pub type SensorSet = Arena<SensorHandle, Sensor>;
+pub type ForceFieldSet = Arena<ForceFieldHandle, ForceField>;
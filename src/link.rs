@@ -1,22 +1,97 @@
-use crate::{BodySet, Handle, Real};
+use crate::{BodyHandle, BodySet, Contact, ContactGenerator, Real};
 
 pub struct Link {
-    pub body_handle: Handle,
-    pub other_body_handle: Handle,
+    pub body_handle: BodyHandle,
+    pub other_body_handle: BodyHandle,
 }
 
 impl Link {
-    pub fn length(&self, bodies: &mut BodySet) -> Real {
+    pub fn length(&self, bodies: &BodySet) -> Real {
         let body = bodies.get(self.body_handle).expect("Failed to get body!");
         let other_body = bodies
             .get(self.other_body_handle)
             .expect("Failed to get body!");
         (body.position - other_body.position).magnitude()
     }
+
+    pub fn references_body(&self, body_handle: BodyHandle) -> bool {
+        self.body_handle == body_handle || self.other_body_handle == body_handle
+    }
 }
 
+/// Links a pair of bodies, generating a contact if they stray too far apart.
 pub struct Cable {
     pub max_length: Real,
     pub restitution: Real,
     pub link: Link,
 }
+
+impl ContactGenerator for Cable {
+    fn add_contact(&self, bodies: &BodySet, contacts: &mut Vec<Contact>, limit: u32) -> u32 {
+        let linked =
+            bodies.contains(self.link.body_handle) && bodies.contains(self.link.other_body_handle);
+        if limit == 0 || !linked {
+            return 0;
+        }
+
+        let length = self.link.length(bodies);
+        if length < self.max_length {
+            return 0;
+        }
+
+        // The cable pulls the body back towards the other body
+        let body_position = bodies[self.link.body_handle].position;
+        let other_body_position = bodies[self.link.other_body_handle].position;
+        contacts.push(Contact {
+            body_handle: self.link.body_handle,
+            other_body_handle: self.link.other_body_handle,
+            restitution: self.restitution,
+            point: body_position,
+            normal: (other_body_position - body_position).normalize(),
+            penetration: length - self.max_length,
+        });
+        1
+    }
+
+    fn references_body(&self, body_handle: BodyHandle) -> bool {
+        self.link.references_body(body_handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Body, PhysicsWorld, Vector3};
+
+    #[test]
+    fn cable_keeps_bodies_within_max_length() {
+        let mut world = PhysicsWorld::default();
+        let anchor = world.add_body(Body::fixed().build().unwrap());
+        let body = world.add_body(
+            Body::dynamic(1.0)
+                .damping(1.0)
+                .position(Vector3::new(1.0, 0.0, 0.0))
+                .velocity(Vector3::new(5.0, 3.0, 0.0))
+                .build()
+                .unwrap(),
+        );
+        world.add_link(Cable {
+            max_length: 2.0,
+            restitution: 0.0,
+            link: Link {
+                body_handle: body,
+                other_body_handle: anchor,
+            },
+        });
+
+        for _ in 0..300 {
+            world.tick(1.0 / 60.0);
+            let length = (world.bodies[body].position - world.bodies[anchor].position).magnitude();
+            assert!(length <= 2.0 + 1e-4, "cable stretched to {}", length);
+        }
+
+        // The cable stops the body rather than just holding it back each tick
+        let outward = world.bodies[body].position.normalize();
+        assert!(world.bodies[body].velocity.dot(outward) <= 1e-4);
+    }
+}
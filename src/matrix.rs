@@ -0,0 +1,68 @@
+use crate::{Real, Vector3};
+use std::ops::{Add, Mul};
+
+/// A 3x3 matrix, stored in row-major order.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Matrix3 {
+    pub data: [Real; 9],
+}
+
+impl Matrix3 {
+    pub fn new(data: [Real; 9]) -> Self {
+        Self { data }
+    }
+
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn identity() -> Self {
+        Self::diagonal(Vector3::new(1.0, 1.0, 1.0))
+    }
+
+    pub fn diagonal(diagonal: Vector3) -> Self {
+        Self::new([
+            diagonal.x, 0.0, 0.0, //
+            0.0, diagonal.y, 0.0, //
+            0.0, 0.0, diagonal.z,
+        ])
+    }
+
+    /// Blends between two matrices, giving `self` at zero and `other` at one.
+    pub fn lerp(&self, other: &Self, t: Real) -> Self {
+        *self * (1.0 - t) + *other * t
+    }
+}
+
+impl Add for Matrix3 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        let mut data = self.data;
+        data.iter_mut()
+            .zip(rhs.data.iter())
+            .for_each(|(value, rhs)| *value += rhs);
+        Self::new(data)
+    }
+}
+
+impl Mul<Real> for Matrix3 {
+    type Output = Self;
+
+    fn mul(self, value: Real) -> Self {
+        Self::new(self.data.map(|element| element * value))
+    }
+}
+
+impl Mul<Vector3> for Matrix3 {
+    type Output = Vector3;
+
+    fn mul(self, vector: Vector3) -> Vector3 {
+        let d = &self.data;
+        Vector3::new(
+            d[0] * vector.x + d[1] * vector.y + d[2] * vector.z,
+            d[3] * vector.x + d[4] * vector.y + d[5] * vector.z,
+            d[6] * vector.x + d[7] * vector.y + d[8] * vector.z,
+        )
+    }
+}
@@ -0,0 +1,403 @@
+use crate::{noise::Random, BodyHandle, BodySet, ForceContext, ForceGenerator, Real, Vector3};
+use std::{any::Any, collections::BTreeMap};
+
+/// The total kinetic energy of the given bodies, ignoring bodies with infinite mass.
+pub fn kinetic_energy(bodies: &BodySet, body_handles: &[BodyHandle]) -> Real {
+    body_handles
+        .iter()
+        .filter_map(|&body_handle| bodies.get(body_handle))
+        .filter(|body| !body.has_infinite_mass())
+        .map(|body| 0.5 * body.mass() * body.velocity.magnitude_squared())
+        .sum()
+}
+
+/// The temperature of the given bodies, from their kinetic
+/// energy shared over three degrees of freedom per body.
+pub fn temperature(
+    bodies: &BodySet,
+    body_handles: &[BodyHandle],
+    boltzmann_constant: Real,
+) -> Real {
+    let count = body_handles
+        .iter()
+        .filter_map(|&body_handle| bodies.get(body_handle))
+        .filter(|body| !body.has_infinite_mass())
+        .count();
+    if count == 0 {
+        return 0.0;
+    }
+    2.0 * kinetic_energy(bodies, body_handles) / (3.0 * count as Real * boltzmann_constant)
+}
+
+/// The Lennard-Jones interaction between every pair of bodies the generator is
+/// registered for, which repels atoms that get too close and weakly attracts them
+/// at a distance.
+///
+/// Pairs further apart than the cutoff don't interact, and the potential is shifted
+/// so that it reaches zero at the cutoff. To avoid checking every pair on every tick,
+/// the generator keeps a list of the pairs within the cutoff plus a skin distance and
+/// only rebuilds it once a body has moved more than half the skin.
+///
+/// Distances use the world's periodic boundary if it has one,
+/// in which case the cutoff should be less than half of the box.
+///
+/// Bodies are integrated with explicit Euler steps, which slowly add energy to
+/// the stiff oscillations of bonded atoms. Simulations left to run for long
+/// should use short ticks or a `Thermostat` to hold the energy steady.
+pub struct LennardJones {
+    /// The depth of the potential well
+    pub epsilon: Real,
+
+    /// The distance at which the potential is zero, roughly the size of an atom
+    pub sigma: Real,
+
+    /// The distance beyond which pairs don't interact
+    pub cutoff: Real,
+
+    /// The extra distance covered by the neighbor list
+    pub skin: Real,
+
+    members: Vec<BodyHandle>,
+    reference_positions: Vec<Vector3>,
+    neighbors: Vec<(usize, usize)>,
+    forces: BTreeMap<BodyHandle, Vector3>,
+    potential_energy: Real,
+}
+
+impl LennardJones {
+    pub fn new(epsilon: Real, sigma: Real) -> Self {
+        Self {
+            epsilon,
+            sigma,
+            cutoff: 2.5 * sigma,
+            skin: 0.3 * sigma,
+            members: Vec::new(),
+            reference_positions: Vec::new(),
+            neighbors: Vec::new(),
+            forces: BTreeMap::new(),
+            potential_energy: 0.0,
+        }
+    }
+
+    /// The unshifted potential energy of a pair at the given distance.
+    pub fn potential(&self, distance: Real) -> Real {
+        let inverse_6 = (self.sigma / distance).powi(6);
+        4.0 * self.epsilon * (inverse_6.powi(2) - inverse_6)
+    }
+
+    /// The total potential energy of the registered bodies as of the last update.
+    pub fn potential_energy(&self) -> Real {
+        self.potential_energy
+    }
+
+    /// The number of pairs in the neighbor list.
+    pub fn neighbor_count(&self) -> usize {
+        self.neighbors.len()
+    }
+
+    fn needs_rebuild(
+        &self,
+        context: &ForceContext,
+        members: &[BodyHandle],
+        positions: &[Vector3],
+    ) -> bool {
+        if members != self.members.as_slice() {
+            return true;
+        }
+
+        let limit = (0.5 * self.skin).powi(2);
+        positions
+            .iter()
+            .zip(&self.reference_positions)
+            .any(|(&position, &reference)| {
+                context.offset(reference, position).magnitude_squared() > limit
+            })
+    }
+
+    fn rebuild(
+        &mut self,
+        context: &ForceContext,
+        members: Vec<BodyHandle>,
+        positions: Vec<Vector3>,
+    ) {
+        let range = (self.cutoff + self.skin).powi(2);
+        self.neighbors.clear();
+        for (i, &position) in positions.iter().enumerate() {
+            for (j, &other_position) in positions.iter().enumerate().skip(i + 1) {
+                if context.offset(other_position, position).magnitude_squared() <= range {
+                    self.neighbors.push((i, j));
+                }
+            }
+        }
+
+        self.members = members;
+        self.reference_positions = positions;
+    }
+}
+
+impl ForceGenerator for LennardJones {
+    fn apply(&mut self, _context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        if let (Some(force), Some(body)) =
+            (self.forces.get(&body_handle), bodies.get_mut(body_handle))
+        {
+            body.add_force(force);
+        }
+    }
+
+    fn update(
+        &mut self,
+        context: &ForceContext,
+        bodies: &BodySet,
+        registered_bodies: &[BodyHandle],
+    ) {
+        let (members, positions): (Vec<_>, Vec<_>) = registered_bodies
+            .iter()
+            .filter_map(|&body_handle| Some((body_handle, bodies.get(body_handle)?.position)))
+            .unzip();
+
+        if self.needs_rebuild(context, &members, &positions) {
+            self.rebuild(context, members, positions.clone());
+        }
+
+        let cutoff_squared = self.cutoff.powi(2);
+        let shift = self.potential(self.cutoff);
+        let mut forces = vec![Vector3::zero(); positions.len()];
+        self.potential_energy = 0.0;
+
+        for &(i, j) in self.neighbors.iter() {
+            let offset = context.offset(positions[j], positions[i]);
+            let distance_squared = offset.magnitude_squared();
+            if distance_squared >= cutoff_squared || distance_squared <= 0.0 {
+                continue;
+            }
+
+            let inverse_6 = (self.sigma.powi(2) / distance_squared).powi(3);
+            let magnitude =
+                24.0 * self.epsilon * (2.0 * inverse_6.powi(2) - inverse_6) / distance_squared;
+            let force = offset * magnitude;
+            forces[i] += force;
+            forces[j] -= force;
+
+            self.potential_energy += 4.0 * self.epsilon * (inverse_6.powi(2) - inverse_6) - shift;
+        }
+
+        self.forces = self.members.iter().copied().zip(forces).collect();
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        Some(self)
+    }
+}
+
+/// How a `Thermostat` steers bodies towards its target temperature.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ThermostatMethod {
+    /// Scales velocities to hit the target temperature exactly on every tick.
+    Rescale,
+
+    /// Scales velocities to approach the target temperature
+    /// exponentially over the time constant.
+    Berendsen { time_constant: Real },
+
+    /// Applies friction along with random kicks that balance it at the target temperature.
+    Langevin { friction: Real },
+}
+
+/// Holds the bodies it is registered for at a target temperature.
+///
+/// The thermostat works by applying forces, so bodies should have a damping of 1.0
+/// for their temperature to settle at the target. Temperatures are in units where
+/// the Boltzmann constant is one unless told otherwise, matching reduced Lennard-Jones units.
+pub struct Thermostat {
+    pub method: ThermostatMethod,
+    pub target_temperature: Real,
+    pub boltzmann_constant: Real,
+
+    /// The factor velocities are scaled by this tick
+    scale: Real,
+
+    random: Random,
+}
+
+impl Thermostat {
+    pub fn new(method: ThermostatMethod, target_temperature: Real) -> Self {
+        Self {
+            method,
+            target_temperature,
+            boltzmann_constant: 1.0,
+            scale: 1.0,
+            random: Random::default(),
+        }
+    }
+
+    /// Seeds the random kicks of the Langevin method.
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.random = Random::new(seed);
+        self
+    }
+}
+
+impl ForceGenerator for Thermostat {
+    fn apply(&mut self, context: &ForceContext, body_handle: BodyHandle, bodies: &mut BodySet) {
+        if context.duration <= 0.0 {
+            return;
+        }
+
+        let body = match bodies.get_mut(body_handle) {
+            Some(body) if !body.has_infinite_mass() => body,
+            _ => return,
+        };
+
+        let force = match self.method {
+            ThermostatMethod::Rescale | ThermostatMethod::Berendsen { .. } => {
+                // The force that scales the velocity over the tick
+                body.velocity * (body.mass() * (self.scale - 1.0) / context.duration)
+            }
+            ThermostatMethod::Langevin { friction } => {
+                let kick = (2.0
+                    * friction
+                    * body.mass()
+                    * self.boltzmann_constant
+                    * self.target_temperature
+                    / context.duration)
+                    .sqrt();
+                let random = Vector3::new(
+                    self.random.gaussian(),
+                    self.random.gaussian(),
+                    self.random.gaussian(),
+                );
+                body.velocity * (-friction * body.mass()) + random * kick
+            }
+        };
+        body.add_force(&force);
+    }
+
+    fn update(
+        &mut self,
+        context: &ForceContext,
+        bodies: &BodySet,
+        registered_bodies: &[BodyHandle],
+    ) {
+        let current = temperature(bodies, registered_bodies, self.boltzmann_constant);
+        if current <= 0.0 {
+            self.scale = 1.0;
+            return;
+        }
+
+        let ratio = self.target_temperature / current;
+        self.scale = match self.method {
+            ThermostatMethod::Rescale => ratio.sqrt(),
+            ThermostatMethod::Berendsen { time_constant } => {
+                let blend = (context.duration / time_constant).min(1.0);
+                (1.0 + blend * (ratio - 1.0)).max(0.0).sqrt()
+            }
+            ThermostatMethod::Langevin { .. } => 1.0,
+        };
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{noise::Random, Body, ForceGeneratorHandle, PhysicsWorld};
+
+    /// A cube of atoms on a lattice with random velocities, all registered with the generator.
+    fn lattice_world<G: ForceGenerator + 'static>(
+        side: usize,
+        spacing: Real,
+        speed: Real,
+        generator: G,
+    ) -> (PhysicsWorld, Vec<BodyHandle>, ForceGeneratorHandle) {
+        let mut world = PhysicsWorld::default();
+        let mut random = Random::new(7);
+        let generator = world.add_force_generator(generator);
+        let mut handles = Vec::new();
+        for i in 0..side.pow(3) {
+            let position = Vector3::new(
+                (i % side) as Real,
+                (i / side % side) as Real,
+                (i / side / side) as Real,
+            ) * spacing;
+            let velocity = Vector3::new(random.signed(), random.signed(), random.signed()) * speed;
+            let body = world.add_body(
+                Body::dynamic(1.0)
+                    .damping(1.0)
+                    .position(position)
+                    .velocity(velocity)
+                    .build()
+                    .unwrap(),
+            );
+            world.register_force(generator, body);
+            handles.push(body);
+        }
+        (world, handles, generator)
+    }
+
+    #[test]
+    fn lennard_jones_conserves_energy() {
+        let (mut world, handles, generator) =
+            lattice_world(3, 1.12, 0.3, LennardJones::new(1.0, 1.0));
+        let energy = |world: &PhysicsWorld| {
+            let potential = world.force_generators[generator]
+                .downcast_ref::<LennardJones>()
+                .unwrap()
+                .potential_energy();
+            kinetic_energy(&world.bodies, &handles) + potential
+        };
+
+        world.tick(0.0002);
+        let initial = energy(&world);
+        for _ in 0..10000 {
+            world.tick(0.0002);
+        }
+
+        // Without a thermostat the total energy only drifts slowly with short ticks
+        let drift = (energy(&world) - initial).abs();
+        assert!(
+            drift < 0.02 * initial.abs(),
+            "{} -> {}",
+            initial,
+            energy(&world)
+        );
+        assert!(kinetic_energy(&world.bodies, &handles) > 0.0);
+    }
+
+    #[test]
+    fn thermostats_reach_the_target_temperature() {
+        let methods = [
+            ThermostatMethod::Rescale,
+            ThermostatMethod::Berendsen { time_constant: 0.1 },
+            ThermostatMethod::Langevin { friction: 5.0 },
+        ];
+
+        for &method in methods.iter() {
+            let (mut world, handles, _) =
+                lattice_world(4, 2.0, 0.5, Thermostat::new(method, 2.0).with_seed(3));
+
+            for _ in 0..1000 {
+                world.tick(0.01);
+            }
+
+            // The Langevin method fluctuates around the target, so average it over a while
+            let mut total = 0.0;
+            for _ in 0..500 {
+                world.tick(0.01);
+                total += temperature(&world.bodies, &handles, 1.0);
+            }
+            let average = total / 500.0;
+            assert!((average - 2.0).abs() < 0.1, "{:?}: {}", method, average);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{Real, Vector3};
+
+/// A small seeded xorshift generator for the random parts of force generators,
+/// so that simulations are repeatable.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Random {
+    state: u32,
+}
+
+impl Default for Random {
+    fn default() -> Self {
+        Self::new(0x2545_f491)
+    }
+}
+
+impl Random {
+    pub(crate) fn new(seed: u32) -> Self {
+        // Xorshift gets stuck at zero
+        Self { state: seed.max(1) }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        let mut state = self.state;
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        self.state = state;
+        state
+    }
+
+    /// A random number between 0 and 1, excluding 0.
+    pub(crate) fn unit(&mut self) -> Real {
+        (self.next_u32() as Real / u32::MAX as Real).max(Real::MIN_POSITIVE)
+    }
+
+    /// A random number between -1 and 1.
+    pub(crate) fn signed(&mut self) -> Real {
+        self.next_u32() as Real / u32::MAX as Real * 2.0 - 1.0
+    }
+
+    /// A normally distributed random number with a mean of 0 and a standard deviation of 1.
+    pub(crate) fn gaussian(&mut self) -> Real {
+        let (first, second) = (self.unit(), self.unit());
+        (-2.0 * first.ln()).sqrt() * (2.0 * std::f32::consts::PI * second).cos()
+    }
+}
+
+/// Hashes a lattice point to a pseudo-random value in [-1, 1].
+fn lattice_value(x: i32, y: i32, z: i32, seed: u32) -> Real {
+    let mut hash = seed
+        ^ (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x7feb_352d);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x846c_a68b);
+    hash ^= hash >> 16;
+    (hash as Real / u32::MAX as Real) * 2.0 - 1.0
+}
+
+fn smoothstep(t: Real) -> Real {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lerp(a: Real, b: Real, t: Real) -> Real {
+    a + (b - a) * t
+}
+
+/// Smoothly interpolated value noise in [-1, 1], varying over a scale of one unit.
+pub(crate) fn value_noise(point: Vector3, seed: u32) -> Real {
+    let (x, y, z) = (point.x.floor(), point.y.floor(), point.z.floor());
+    let (ix, iy, iz) = (x as i32, y as i32, z as i32);
+    let (tx, ty, tz) = (
+        smoothstep(point.x - x),
+        smoothstep(point.y - y),
+        smoothstep(point.z - z),
+    );
+
+    let corner = |dx, dy, dz| lattice_value(ix + dx, iy + dy, iz + dz, seed);
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), tx);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), tx);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), tx);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), tx);
+    lerp(lerp(x00, x10, ty), lerp(x01, x11, ty), tz)
+}
+
+/// The curl of a noise-based vector potential, scaled to an average magnitude of one.
+/// The largest magnitudes are around three.
+///
+/// Being the curl of another field, the result is divergence free,
+/// which gives swirling eddies without sources or sinks.
+pub(crate) fn curl_noise(point: Vector3, seed: u32) -> Vector3 {
+    const EPSILON: Real = 1e-2;
+
+    // The measured average magnitude of the raw curl
+    const AVERAGE_MAGNITUDE: Real = 1.5;
+
+    let potential = |point: Vector3| {
+        Vector3::new(
+            value_noise(point, seed),
+            value_noise(point, seed.wrapping_add(1)),
+            value_noise(point, seed.wrapping_add(2)),
+        )
+    };
+
+    // Central differences of the potential along each axis
+    let derivative = |axis: Vector3| {
+        (potential(point + axis * EPSILON) - potential(point - axis * EPSILON)) / (2.0 * EPSILON)
+    };
+    let dx = derivative(Vector3::x());
+    let dy = derivative(Vector3::y());
+    let dz = derivative(Vector3::z());
+
+    Vector3::new(dy.z - dz.y, dz.x - dx.z, dx.y - dy.x) / AVERAGE_MAGNITUDE
+}
@@ -0,0 +1,101 @@
+use crate::{Aabb, Real, Vector3};
+
+/// A box whose opposite faces are joined, so that bodies leaving through
+/// one face come back in through the other.
+///
+/// Distances between bodies follow the minimum image convention, measuring to
+/// whichever copy of the other body is nearest. Only force generators that use
+/// `ForceContext::boundary` take this into account. Contacts, sensors and spatial
+/// queries still work in plain space.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PeriodicBoundary {
+    pub bounds: Aabb,
+}
+
+impl PeriodicBoundary {
+    pub fn new(bounds: Aabb) -> Self {
+        Self { bounds }
+    }
+
+    /// A box with one corner at the origin.
+    pub fn from_size(size: Vector3) -> Self {
+        Self::new(Aabb::new(Vector3::zero(), size))
+    }
+
+    pub fn size(&self) -> Vector3 {
+        self.bounds.max - self.bounds.min
+    }
+
+    /// The volume enclosed by the box.
+    pub fn volume(&self) -> Real {
+        let size = self.size();
+        size.x * size.y * size.z
+    }
+
+    /// Moves a position back into the box.
+    pub fn wrap(&self, position: Vector3) -> Vector3 {
+        let wrap = |value: Real, min: Real, size: Real| {
+            if size > 0.0 {
+                min + (value - min).rem_euclid(size)
+            } else {
+                value
+            }
+        };
+        let size = self.size();
+        Vector3::new(
+            wrap(position.x, self.bounds.min.x, size.x),
+            wrap(position.y, self.bounds.min.y, size.y),
+            wrap(position.z, self.bounds.min.z, size.z),
+        )
+    }
+
+    /// The shortest equivalent of an offset between two positions in the box.
+    pub fn minimum_image(&self, offset: Vector3) -> Vector3 {
+        let image = |value: Real, size: Real| {
+            if size > 0.0 {
+                value - size * (value / size).round()
+            } else {
+                value
+            }
+        };
+        let size = self.size();
+        Vector3::new(
+            image(offset.x, size.x),
+            image(offset.y, size.y),
+            image(offset.z, size.z),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_moves_positions_into_the_box() {
+        let boundary = PeriodicBoundary::new(Aabb::new(
+            Vector3::new(-1.0, 0.0, 2.0),
+            Vector3::new(3.0, 2.0, 2.0),
+        ));
+
+        let wrapped = boundary.wrap(Vector3::new(3.5, -0.5, 7.0));
+        assert!((wrapped - Vector3::new(-0.5, 1.5, 7.0)).magnitude() < 1e-5);
+
+        // Positions inside the box are left alone, and flat axes never wrap
+        let inside = Vector3::new(0.0, 1.0, 2.0);
+        assert_eq!(boundary.wrap(inside), inside);
+    }
+
+    #[test]
+    fn minimum_image_measures_to_the_nearest_copy() {
+        let boundary = PeriodicBoundary::from_size(Vector3::new(10.0, 10.0, 10.0));
+
+        let image = boundary.minimum_image(Vector3::new(9.0, -6.0, 3.0));
+        assert!((image - Vector3::new(-1.0, 4.0, 3.0)).magnitude() < 1e-5);
+
+        // The offset between positions on either side of a face goes through the face
+        let offset = Vector3::new(0.5, 5.0, 5.0) - Vector3::new(9.5, 5.0, 5.0);
+        let image = boundary.minimum_image(offset);
+        assert!((image - Vector3::new(1.0, 0.0, 0.0)).magnitude() < 1e-5);
+    }
+}
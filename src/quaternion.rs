@@ -0,0 +1,101 @@
+use crate::{Real, Vector3};
+use std::ops::Mul;
+
+/// A rotation in three dimensions, stored as a unit quaternion.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quaternion {
+    pub w: Real,
+    pub x: Real,
+    pub y: Real,
+    pub z: Real,
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Quaternion {
+    pub fn new(w: Real, x: Real, y: Real, z: Real) -> Self {
+        Self { w, x, y, z }
+    }
+
+    /// The rotation that leaves everything where it is.
+    pub fn identity() -> Self {
+        Self::new(1.0, 0.0, 0.0, 0.0)
+    }
+
+    /// A rotation by an angle in radians, counterclockwise about an axis when viewed from its tip.
+    pub fn from_axis_angle(axis: Vector3, angle: Real) -> Self {
+        let axis = axis.normalize();
+        let (sin, cos) = (0.5 * angle).sin_cos();
+        Self::new(cos, axis.x * sin, axis.y * sin, axis.z * sin)
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.w.is_finite() && self.x.is_finite() && self.y.is_finite() && self.z.is_finite()
+    }
+
+    pub fn magnitude(&self) -> Real {
+        (self.w.powi(2) + self.x.powi(2) + self.y.powi(2) + self.z.powi(2)).sqrt()
+    }
+
+    /// Scales the quaternion to unit length, which makes it a valid rotation.
+    /// A zero quaternion becomes the identity.
+    pub fn normalize(&self) -> Self {
+        let magnitude = self.magnitude();
+        if magnitude <= 0.0 {
+            return Self::identity();
+        }
+        Self::new(
+            self.w / magnitude,
+            self.x / magnitude,
+            self.y / magnitude,
+            self.z / magnitude,
+        )
+    }
+
+    /// The opposite rotation.
+    pub fn conjugate(&self) -> Self {
+        Self::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    /// Rotates a vector.
+    pub fn rotate(&self, vector: Vector3) -> Vector3 {
+        let axis = Vector3::new(self.x, self.y, self.z);
+        let twice_cross = axis.cross(vector) * 2.0;
+        vector + twice_cross * self.w + axis.cross(twice_cross)
+    }
+
+    /// Turns the rotation by an angular velocity over the given duration.
+    /// The result drifts from unit length and should be normalized afterwards.
+    pub fn integrate(&self, angular_velocity: Vector3, duration: Real) -> Self {
+        let spin = Self::new(
+            0.0,
+            angular_velocity.x * duration,
+            angular_velocity.y * duration,
+            angular_velocity.z * duration,
+        ) * *self;
+        Self::new(
+            self.w + 0.5 * spin.w,
+            self.x + 0.5 * spin.x,
+            self.y + 0.5 * spin.y,
+            self.z + 0.5 * spin.z,
+        )
+    }
+}
+
+impl Mul for Quaternion {
+    type Output = Self;
+
+    /// Combines two rotations, applying `rhs` first.
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+        )
+    }
+}
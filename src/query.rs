@@ -0,0 +1,75 @@
+use crate::{Body, BodyHandle, CollisionFilter, Real, Vector3};
+
+/// A predicate deciding whether a body takes part in a query.
+pub type QueryPredicate<'a> = &'a dyn Fn(BodyHandle, &Body) -> bool;
+
+/// The closest point of impact found by a ray or shape cast.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RaycastHit {
+    pub body_handle: BodyHandle,
+
+    /// The point of impact on the surface of the body that was hit
+    pub point: Vector3,
+
+    /// The surface normal of the body that was hit, in world coordinates
+    pub normal: Vector3,
+
+    /// The distance travelled along the cast direction before the hit
+    pub distance: Real,
+}
+
+/// Restricts the bodies considered by a spatial query.
+#[derive(Copy, Clone)]
+pub struct QueryFilter<'a> {
+    /// The layers to search. Only bodies that are members of
+    /// at least one of these layers are considered.
+    pub layers: u32,
+
+    /// A body to ignore, such as the body performing the query
+    pub exclude: Option<BodyHandle>,
+
+    /// A predicate that bodies must satisfy to be considered
+    pub predicate: Option<QueryPredicate<'a>>,
+}
+
+impl Default for QueryFilter<'_> {
+    fn default() -> Self {
+        Self {
+            layers: CollisionFilter::ALL,
+            exclude: None,
+            predicate: None,
+        }
+    }
+}
+
+impl<'a> QueryFilter<'a> {
+    pub fn layers(mut self, layers: u32) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    pub fn exclude(mut self, body_handle: BodyHandle) -> Self {
+        self.exclude = Some(body_handle);
+        self
+    }
+
+    pub fn predicate(mut self, predicate: QueryPredicate<'a>) -> Self {
+        self.predicate = Some(predicate);
+        self
+    }
+
+    pub fn accepts(&self, body_handle: BodyHandle, body: &Body) -> bool {
+        if self.exclude == Some(body_handle) {
+            return false;
+        }
+
+        if body.collision_filter.memberships & self.layers == 0 {
+            return false;
+        }
+
+        match self.predicate {
+            Some(predicate) => predicate(body_handle, body),
+            None => true,
+        }
+    }
+}
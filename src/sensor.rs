@@ -0,0 +1,51 @@
+use crate::{BodyHandle, CollisionFilter, Volume};
+use std::collections::BTreeSet;
+
+/// A region that reports bodies entering and leaving it
+/// without producing any physical response.
+///
+/// Occupants are refreshed at the end of every world tick.
+pub struct Sensor {
+    pub volume: Volume,
+
+    /// Controls which bodies the sensor detects
+    pub collision_filter: CollisionFilter,
+
+    occupants: BTreeSet<BodyHandle>,
+}
+
+impl Sensor {
+    pub fn new(volume: Volume) -> Self {
+        Self {
+            volume,
+            collision_filter: CollisionFilter::default(),
+            occupants: BTreeSet::new(),
+        }
+    }
+
+    pub fn with_collision_filter(mut self, collision_filter: CollisionFilter) -> Self {
+        self.collision_filter = collision_filter;
+        self
+    }
+
+    /// The bodies inside the sensor as of the last tick.
+    pub fn occupants(&self) -> impl Iterator<Item = BodyHandle> + '_ {
+        self.occupants.iter().copied()
+    }
+
+    pub fn contains(&self, body_handle: BodyHandle) -> bool {
+        self.occupants.contains(&body_handle)
+    }
+
+    /// Replaces the occupants, returning the bodies
+    /// that entered and exited the sensor.
+    pub(crate) fn set_occupants(
+        &mut self,
+        occupants: BTreeSet<BodyHandle>,
+    ) -> (Vec<BodyHandle>, Vec<BodyHandle>) {
+        let entered = occupants.difference(&self.occupants).copied().collect();
+        let exited = self.occupants.difference(&occupants).copied().collect();
+        self.occupants = occupants;
+        (entered, exited)
+    }
+}
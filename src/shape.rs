@@ -0,0 +1,165 @@
+use crate::{Aabb, Real, Vector3};
+
+/// The geometry attached to a body.
+///
+/// Shapes are positioned at the body's position and
+/// are used by the world's spatial queries.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Shape {
+    /// A sphere centered on the body.
+    /// A radius of zero represents a point particle.
+    Sphere { radius: Real },
+
+    /// An infinite plane passing through the body with the given unit normal.
+    Plane { normal: Vector3 },
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Self::Sphere { radius: 0.0 }
+    }
+}
+
+impl Shape {
+    pub fn sphere(radius: Real) -> Self {
+        Self::Sphere { radius }
+    }
+
+    pub fn plane(normal: Vector3) -> Self {
+        Self::Plane {
+            normal: normal.normalize(),
+        }
+    }
+
+    /// The volume enclosed by the shape. Planes enclose an infinite volume.
+    pub fn volume(&self) -> Real {
+        match *self {
+            Self::Sphere { radius } => 4.0 / 3.0 * std::f32::consts::PI * radius.powi(3),
+            Self::Plane { .. } => Real::INFINITY,
+        }
+    }
+
+    /// The area of the silhouette the shape presents to oncoming air or water.
+    /// Planes present an infinite area.
+    pub fn cross_sectional_area(&self) -> Real {
+        match *self {
+            Self::Sphere { radius } => std::f32::consts::PI * radius.powi(2),
+            Self::Plane { .. } => Real::INFINITY,
+        }
+    }
+
+    /// The bounds of the shape placed at the given position.
+    /// Unbounded shapes such as planes have no bounding box.
+    pub fn aabb(&self, position: Vector3) -> Option<Aabb> {
+        match *self {
+            Self::Sphere { radius } => Some(Aabb::from_sphere(position, radius)),
+            Self::Plane { .. } => None,
+        }
+    }
+
+    /// The distance from a point to the surface of the shape placed at the given position.
+    /// Points inside a sphere are at a distance of zero.
+    pub fn distance_to_point(&self, position: Vector3, point: Vector3) -> Real {
+        match *self {
+            Self::Sphere { radius } => ((point - position).magnitude() - radius).max(0.0),
+            Self::Plane { normal } => normal.dot(point - position).abs(),
+        }
+    }
+
+    /// Checks whether the shape placed at the given position overlaps a box.
+    pub fn intersects_aabb(&self, position: Vector3, aabb: &Aabb) -> bool {
+        match *self {
+            Self::Sphere { radius } => aabb.distance_squared_to_point(position) <= radius.powi(2),
+            Self::Plane { normal } => {
+                // Project the box onto the plane normal and check if it straddles the plane
+                let extents = aabb.half_extents();
+                let projected_radius = extents.x * normal.x.abs()
+                    + extents.y * normal.y.abs()
+                    + extents.z * normal.z.abs();
+                normal.dot(aabb.center() - position).abs() <= projected_radius
+            }
+        }
+    }
+
+    /// Casts a ray against the shape placed at the given position.
+    ///
+    /// The direction must be normalized. Returns the distance along the
+    /// ray and the surface normal at the point of impact.
+    pub fn cast_ray(
+        &self,
+        position: Vector3,
+        origin: Vector3,
+        direction: Vector3,
+        max_distance: Real,
+    ) -> Option<(Real, Vector3)> {
+        self.cast_sphere(position, origin, direction, 0.0, max_distance)
+    }
+
+    /// Sweeps a sphere of the given radius against the shape placed at the given position.
+    ///
+    /// This is equivalent to casting a ray against the shape inflated by the radius.
+    /// The direction must be normalized. Returns the distance travelled by the
+    /// sphere's center and the surface normal at the point of impact.
+    pub fn cast_sphere(
+        &self,
+        position: Vector3,
+        origin: Vector3,
+        direction: Vector3,
+        radius: Real,
+        max_distance: Real,
+    ) -> Option<(Real, Vector3)> {
+        let (distance, normal) = match *self {
+            Self::Sphere { radius: own_radius } => {
+                let radius = own_radius + radius;
+                let offset = origin - position;
+                let projection = offset.dot(direction);
+                let excess = offset.magnitude_squared() - radius.powi(2);
+
+                // The ray starts outside the sphere and points away from it
+                if excess > 0.0 && projection > 0.0 {
+                    return None;
+                }
+
+                let discriminant = projection.powi(2) - excess;
+                if discriminant < 0.0 {
+                    return None;
+                }
+
+                // A ray starting inside the sphere hits immediately
+                let distance = (-projection - discriminant.sqrt()).max(0.0);
+                let normal = (origin + direction * distance - position).normalize();
+                if normal.magnitude_squared() > 0.0 {
+                    (distance, normal)
+                } else {
+                    (distance, direction.inverse())
+                }
+            }
+            Self::Plane { normal } => {
+                // Planes are two-sided, so face the normal towards the ray's origin
+                let height = normal.dot(origin - position);
+                let normal = if height >= 0.0 {
+                    normal
+                } else {
+                    normal.inverse()
+                };
+
+                let gap = height.abs() - radius;
+                if gap <= 0.0 {
+                    (0.0, normal)
+                } else {
+                    let approach_speed = -normal.dot(direction);
+                    if approach_speed <= 0.0 {
+                        return None;
+                    }
+                    (gap / approach_speed, normal)
+                }
+            }
+        };
+
+        if distance > max_distance {
+            return None;
+        }
+
+        Some((distance, normal))
+    }
+}
@@ -33,6 +33,10 @@ impl Vector3 {
         Self::new(-self.x, -self.y, -self.z)
     }
 
+    pub fn is_finite(&self) -> bool {
+        self.x.is_finite() && self.y.is_finite() && self.z.is_finite()
+    }
+
     pub fn magnitude(&self) -> Real {
         self.magnitude_squared().sqrt()
     }
@@ -50,6 +54,16 @@ impl Vector3 {
         }
     }
 
+    /// Shortens the vector to the given length if it is any longer.
+    pub fn clamp_magnitude(&self, max: Real) -> Self {
+        let length = self.magnitude();
+        if length > max {
+            *self * (max / length)
+        } else {
+            *self
+        }
+    }
+
     pub fn dot(&self, rhs: Self) -> f32 {
         self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
     }
@@ -0,0 +1,236 @@
+use crate::{Aabb, Real, Shape, Vector3};
+
+/// A region of space.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Volume {
+    Sphere {
+        center: Vector3,
+        radius: Real,
+    },
+
+    Box(Aabb),
+
+    /// A solid cylinder extending from the center of its base along its axis.
+    Cylinder {
+        base: Vector3,
+        axis: Vector3,
+        height: Real,
+        radius: Real,
+    },
+
+    /// Everything on the opposite side of a plane to its normal,
+    /// such as the water below a surface with an upward normal.
+    HalfSpace {
+        point: Vector3,
+        normal: Vector3,
+    },
+}
+
+impl Volume {
+    pub fn sphere(center: Vector3, radius: Real) -> Self {
+        Self::Sphere { center, radius }
+    }
+
+    pub fn cylinder(base: Vector3, axis: Vector3, height: Real, radius: Real) -> Self {
+        Self::Cylinder {
+            base,
+            axis: axis.normalize(),
+            height,
+            radius,
+        }
+    }
+
+    pub fn half_space(point: Vector3, normal: Vector3) -> Self {
+        Self::HalfSpace {
+            point,
+            normal: normal.normalize(),
+        }
+    }
+
+    /// The bounds of the volume. Half-spaces are unbounded.
+    pub fn aabb(&self) -> Option<Aabb> {
+        match *self {
+            Self::Sphere { center, radius } => Some(Aabb::from_sphere(center, radius)),
+            Self::Box(aabb) => Some(aabb),
+            Self::Cylinder {
+                base,
+                axis,
+                height,
+                radius,
+            } => {
+                // Each end cap extends sideways less the more the axis points along a direction
+                let cap = |axis: Real| radius * (1.0 - axis.powi(2)).max(0.0).sqrt();
+                let cap_extents = Vector3::new(cap(axis.x), cap(axis.y), cap(axis.z));
+                let top = base + axis * height;
+                let caps = Aabb::from_center(base, cap_extents);
+                Some(caps.merge(&Aabb::from_center(top, cap_extents)))
+            }
+            Self::HalfSpace { .. } => None,
+        }
+    }
+
+    pub fn contains_point(&self, point: Vector3) -> bool {
+        match *self {
+            Self::Sphere { center, radius } => {
+                (point - center).magnitude_squared() <= radius.powi(2)
+            }
+            Self::Box(aabb) => aabb.contains_point(point),
+            Self::Cylinder { .. } => self.depth(point) >= 0.0,
+            Self::HalfSpace {
+                point: surface_point,
+                normal,
+            } => normal.dot(point - surface_point) <= 0.0,
+        }
+    }
+
+    /// Checks whether a shape placed at the given position overlaps the volume.
+    pub fn intersects_shape(&self, shape: &Shape, position: Vector3) -> bool {
+        match *self {
+            Self::Sphere { center, radius } => shape.distance_to_point(position, center) <= radius,
+            Self::Box(aabb) => shape.intersects_aabb(position, &aabb),
+            Self::Cylinder {
+                base,
+                axis,
+                height,
+                radius: cylinder_radius,
+            } => match *shape {
+                Shape::Sphere { radius } => {
+                    let closest =
+                        closest_point_in_cylinder(base, axis, height, cylinder_radius, position);
+                    (closest - position).magnitude_squared() <= radius.powi(2)
+                }
+
+                // Conservatively treat the cylinder as its bounds
+                Shape::Plane { .. } => self
+                    .aabb()
+                    .is_some_and(|aabb| shape.intersects_aabb(position, &aabb)),
+            },
+            Self::HalfSpace { point, normal } => {
+                let height = normal.dot(position - point);
+                match *shape {
+                    Shape::Sphere { radius } => height <= radius,
+                    Shape::Plane {
+                        normal: plane_normal,
+                    } => {
+                        // Only a plane parallel to the surface can avoid crossing into the volume
+                        let parallel =
+                            normal.cross(plane_normal).magnitude_squared() <= Real::EPSILON;
+                        !parallel || height <= 0.0
+                    }
+                }
+            }
+        }
+    }
+
+    /// How far inside the volume a point is, measured to the nearest part of its boundary.
+    /// Points outside the volume have a negative depth.
+    pub fn depth(&self, point: Vector3) -> Real {
+        match *self {
+            Self::Sphere { center, radius } => radius - (point - center).magnitude(),
+            Self::Box(aabb) => {
+                let offset = point - aabb.center();
+                let extents = aabb.half_extents();
+                let inside = (extents.x - offset.x.abs())
+                    .min(extents.y - offset.y.abs())
+                    .min(extents.z - offset.z.abs());
+                if inside >= 0.0 {
+                    inside
+                } else {
+                    -aabb.distance_squared_to_point(point).sqrt()
+                }
+            }
+            Self::Cylinder {
+                base,
+                axis,
+                height,
+                radius,
+            } => {
+                let offset = point - base;
+                let along = offset.dot(axis);
+                let across = (offset - axis * along).magnitude();
+                let axial = (0.5 * height) - (along - 0.5 * height).abs();
+                let radial = radius - across;
+                if axial >= 0.0 || radial >= 0.0 {
+                    axial.min(radial)
+                } else {
+                    // Beyond the rim, the nearest boundary is the edge of a cap
+                    -(axial.powi(2) + radial.powi(2)).sqrt()
+                }
+            }
+            Self::HalfSpace {
+                point: surface_point,
+                normal,
+            } => -normal.dot(point - surface_point),
+        }
+    }
+}
+
+/// The point in a cylinder closest to the given point.
+fn closest_point_in_cylinder(
+    base: Vector3,
+    axis: Vector3,
+    height: Real,
+    radius: Real,
+    point: Vector3,
+) -> Vector3 {
+    let offset = point - base;
+    let along = offset.dot(axis);
+    let across = offset - axis * along;
+    let distance = across.magnitude();
+    let across = if distance > radius {
+        across * (radius / distance)
+    } else {
+        across
+    };
+    base + axis * along.max(0.0).min(height) + across
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cylinder() -> Volume {
+        Volume::cylinder(Vector3::zero(), Vector3::y(), 4.0, 1.0)
+    }
+
+    #[test]
+    fn cylinder_depth_at_caps_and_rim() {
+        let cylinder = cylinder();
+        let depth = |x, y| cylinder.depth(Vector3::new(x, y, 0.0));
+
+        // Inside, the nearest boundary is the side
+        assert_eq!(depth(0.0, 2.0), 1.0);
+        assert_eq!(depth(0.0, 0.5), 0.5);
+
+        // On the caps and the rim
+        assert_eq!(depth(0.0, 4.0), 0.0);
+        assert_eq!(depth(0.5, 0.0), 0.0);
+        assert_eq!(depth(1.0, 2.0), 0.0);
+
+        // Beyond a cap, beside the side, and diagonally past the edge of a cap
+        assert_eq!(depth(0.0, 5.0), -1.0);
+        assert_eq!(depth(0.0, -0.5), -0.5);
+        assert_eq!(depth(3.0, 2.0), -2.0);
+        assert!((depth(2.0, 5.0) + Real::sqrt(2.0)).abs() < 1e-6);
+
+        assert!(cylinder.contains_point(Vector3::new(0.0, 4.0, 1.0)));
+        assert!(!cylinder.contains_point(Vector3::new(0.8, 4.0, 0.8)));
+    }
+
+    #[test]
+    fn spheres_intersect_cylinders_at_caps_and_rim() {
+        let cylinder = cylinder();
+        let intersects =
+            |x, y| cylinder.intersects_shape(&Shape::sphere(0.5), Vector3::new(x, y, 0.0));
+
+        assert!(intersects(0.0, 4.4));
+        assert!(!intersects(0.0, 4.6));
+        assert!(intersects(0.0, -0.4));
+        assert!(intersects(1.4, 2.0));
+        assert!(!intersects(1.6, 2.0));
+
+        // Near the edge of a cap, the distance is to the rim rather than to the cap or side
+        assert!(intersects(1.3, 4.3));
+        assert!(!intersects(1.4, 4.4));
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{Real, Vector3};
+use std::f32::consts::PI;
+
+/// The height of a water surface at a horizontal position and time, given as `(x, z, time)`.
+pub type HeightFunction = Box<dyn Fn(Real, Real, Real) -> Real>;
+
+/// A travelling wave on the surface of a body of water.
+#[derive(Debug, Copy, Clone)]
+pub struct Wave {
+    /// The horizontal direction the wave travels in. Only its x and z components are used.
+    pub direction: Vector3,
+
+    /// The height of a crest above the mean water level
+    pub amplitude: Real,
+
+    /// The distance between crests
+    pub wavelength: Real,
+
+    /// How fast crests travel in meters per second
+    pub speed: Real,
+}
+
+impl Wave {
+    pub fn new(direction: Vector3, amplitude: Real, wavelength: Real, speed: Real) -> Self {
+        Self {
+            direction,
+            amplitude,
+            wavelength,
+            speed,
+        }
+    }
+
+    fn wave_number(&self) -> Real {
+        2.0 * PI / self.wavelength
+    }
+
+    fn horizontal_direction(&self) -> Vector3 {
+        Vector3::new(self.direction.x, 0.0, self.direction.z).normalize()
+    }
+
+    /// The phase of the wave at a horizontal position and time.
+    fn phase(&self, x: Real, z: Real, time: Real) -> Real {
+        let direction = self.horizontal_direction();
+        self.wave_number() * (direction.x * x + direction.z * z - self.speed * time)
+    }
+}
+
+/// The surface of a body of water, with the water lying below it.
+pub enum WaterSurface {
+    /// A flat surface through a point. The water lies on the opposite side of the normal.
+    Plane { point: Vector3, normal: Vector3 },
+
+    /// A horizontal surface whose height is given by a function of position and time.
+    Height(HeightFunction),
+
+    /// A horizontal surface displaced vertically by a sum of sine waves.
+    Sine { height: Real, waves: Vec<Wave> },
+
+    /// A horizontal surface displaced by a sum of Gerstner waves, which gather
+    /// water towards their crests to give sharper peaks and flatter troughs.
+    Gerstner {
+        height: Real,
+        waves: Vec<Wave>,
+
+        /// How sharp the crests are, from 0 for sine waves up to 1 for cusps
+        steepness: Real,
+    },
+}
+
+impl WaterSurface {
+    /// A flat horizontal surface at the given height.
+    pub fn flat(height: Real) -> Self {
+        Self::Plane {
+            point: Vector3::new(0.0, height, 0.0),
+            normal: Vector3::y(),
+        }
+    }
+
+    /// The direction pointing out of the water.
+    pub fn up(&self) -> Vector3 {
+        match self {
+            Self::Plane { normal, .. } => normal.normalize(),
+            _ => Vector3::y(),
+        }
+    }
+
+    /// How far below the surface a point is at the given time, measured
+    /// along the up direction. Points above the surface have a negative depth.
+    pub fn depth(&self, point: Vector3, time: Real) -> Real {
+        match self {
+            Self::Plane { point: origin, .. } => (*origin - point).dot(self.up()),
+            _ => self.height(point.x, point.z, time) - point.y,
+        }
+    }
+
+    /// The height of the surface above a horizontal position at the given time.
+    /// Planes that aren't horizontal are intersected with the vertical line through the position.
+    pub fn height(&self, x: Real, z: Real, time: Real) -> Real {
+        match self {
+            Self::Plane { point, normal } => {
+                if normal.y.abs() <= Real::EPSILON {
+                    return Real::INFINITY;
+                }
+                point.y - (normal.x * (x - point.x) + normal.z * (z - point.z)) / normal.y
+            }
+            Self::Height(height) => height(x, z, time),
+            Self::Sine { height, waves } => {
+                height
+                    + waves
+                        .iter()
+                        .map(|wave| wave.amplitude * wave.phase(x, z, time).sin())
+                        .sum::<Real>()
+            }
+            Self::Gerstner {
+                height,
+                waves,
+                steepness,
+            } => {
+                // Gerstner waves move water horizontally as well as vertically, so first find
+                // the undisplaced position that ends up above the one asked about
+                let (mut rest_x, mut rest_z) = (x, z);
+                for _ in 0..4 {
+                    let offset = Self::gerstner_offset(waves, *steepness, rest_x, rest_z, time);
+                    rest_x = x - offset.x;
+                    rest_z = z - offset.z;
+                }
+
+                height + Self::gerstner_offset(waves, *steepness, rest_x, rest_z, time).y
+            }
+        }
+    }
+
+    /// The displacement of the water that rests at a horizontal position.
+    fn gerstner_offset(waves: &[Wave], steepness: Real, x: Real, z: Real, time: Real) -> Vector3 {
+        let count = waves.len() as Real;
+        waves.iter().fold(Vector3::zero(), |offset, wave| {
+            let phase = wave.phase(x, z, time);
+            let horizontal = steepness / (wave.wave_number() * count) * phase.cos();
+            let direction = wave.horizontal_direction();
+            offset
+                + Vector3::new(
+                    direction.x * horizontal,
+                    wave.amplitude * phase.sin(),
+                    direction.z * horizontal,
+                )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waves_move_the_surface() {
+        let wave = Wave::new(Vector3::x(), 0.5, 4.0, 2.0);
+        let sine = WaterSurface::Sine {
+            height: 1.0,
+            waves: vec![wave],
+        };
+        assert!((sine.height(1.0, 0.0, 0.0) - 1.5).abs() < 1e-5);
+        assert!((sine.height(1.0, 7.0, 1.0) - 0.5).abs() < 1e-5);
+
+        // Without steepness Gerstner waves match sine waves
+        let gerstner = |steepness| WaterSurface::Gerstner {
+            height: 1.0,
+            waves: vec![wave],
+            steepness,
+        };
+        assert!((gerstner(0.0).height(0.3, 0.0, 0.7) - sine.height(0.3, 0.0, 0.7)).abs() < 1e-5);
+
+        // Steep waves have crests narrower than their troughs
+        let steep = gerstner(0.8);
+        let samples = 400;
+        let above = (0..samples)
+            .filter(|&i| steep.height(i as Real * 4.0 / samples as Real, 0.0, 0.0) > 1.0)
+            .count();
+        assert!(above < samples / 2);
+    }
+}
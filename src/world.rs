@@ -1,27 +1,1173 @@
-use crate::{BodySet, ForceGeneratorSet, ForceRegistration, Real};
+use crate::{
+    Aabb, Body, BodyHandle, BodySet, BodyType, Broadphase, Cable, CollisionFilter, Contact,
+    ContactEvent, ContactEventKind, ContactGenerator, ContactGeneratorHandle, ContactGeneratorSet,
+    ContactResolver, FnForce, ForceContext, ForceField, ForceFieldHandle, ForceFieldSet,
+    ForceGenerator, ForceGeneratorHandle, ForceGeneratorSet, ForceRegistration,
+    GlobalForceRegistration, LinkHandle, LinkSet, PairFilter, PeriodicBoundary, QueryFilter,
+    RaycastHit, Real, Sensor, SensorEvent, SensorEventKind, SensorHandle, SensorSet, Vector3,
+};
+use std::collections::{BTreeMap, BTreeSet};
 
 #[derive(Default)]
 pub struct PhysicsWorld {
     pub bodies: BodySet,
     pub force_generators: ForceGeneratorSet,
     pub registrations: Vec<ForceRegistration>,
+    pub global_registrations: Vec<GlobalForceRegistration>,
+    pub contact_generators: ContactGeneratorSet,
+
+    /// Links between pairs of bodies, such as cables.
+    /// Links hold regardless of collision filters and the pair filter.
+    pub links: LinkSet,
+    pub contact_resolver: ContactResolver,
+
+    /// The maximum number of contacts generated each tick, if any
+    pub max_contacts: Option<u32>,
+
+    /// Decides whether a pair of bodies may interact, in addition to their collision filters
+    pub pair_filter: Option<PairFilter>,
+
+    pub sensors: SensorSet,
+
+    /// Force fields apply to whatever bodies are inside them, without being registered
+    pub force_fields: ForceFieldSet,
+
+    /// Wraps dynamic bodies around a box at the end of every tick, if set
+    pub boundary: Option<PeriodicBoundary>,
+
+    contacts: Vec<Contact>,
+    touching: BTreeMap<(BodyHandle, BodyHandle), ContactEvent>,
+    contact_events: Vec<ContactEvent>,
+    sensor_events: Vec<SensorEvent>,
+    broadphase: Broadphase,
+    elapsed: Real,
+    tick_count: u64,
 }
 
 impl PhysicsWorld {
+    pub fn add_body(&mut self, body: Body) -> BodyHandle {
+        let body_handle = self.bodies.insert(body);
+        self.broadphase
+            .update_body(body_handle, &self.bodies[body_handle]);
+        body_handle
+    }
+
+    /// Removes a body along with everything that depends on it.
+    ///
+    /// The body is unregistered from every force generator, and any force generators,
+    /// contact generators and links that reference it, such as springs and cables, are removed.
+    /// Contacts and sensor occupancy involving the body end on the next tick.
+    pub fn remove_body(&mut self, body_handle: BodyHandle) -> Option<Body> {
+        let body = self.bodies.remove(body_handle)?;
+
+        for registration in self.registrations.iter_mut() {
+            registration
+                .bodies
+                .retain(|registered_handle| *registered_handle != body_handle);
+        }
+
+        let dependent_force_generators = self
+            .force_generators
+            .iter()
+            .filter(|(_, force_generator)| force_generator.references_body(body_handle))
+            .map(|(generator_handle, _)| generator_handle)
+            .collect::<Vec<_>>();
+        for generator_handle in dependent_force_generators {
+            self.remove_force_generator(generator_handle);
+        }
+
+        let dependent_force_fields = self
+            .force_fields
+            .iter()
+            .filter(|(_, force_field)| force_field.references_body(body_handle))
+            .map(|(field_handle, _)| field_handle)
+            .collect::<Vec<_>>();
+        for field_handle in dependent_force_fields {
+            self.force_fields.remove(field_handle);
+        }
+
+        let dependent_contact_generators = self
+            .contact_generators
+            .iter()
+            .filter(|(_, contact_generator)| contact_generator.references_body(body_handle))
+            .map(|(generator_handle, _)| generator_handle)
+            .collect::<Vec<_>>();
+        for generator_handle in dependent_contact_generators {
+            self.contact_generators.remove(generator_handle);
+        }
+
+        let dependent_links = self
+            .links
+            .iter()
+            .filter(|(_, link)| link.references_body(body_handle))
+            .map(|(link_handle, _)| link_handle)
+            .collect::<Vec<_>>();
+        for link_handle in dependent_links {
+            self.links.remove(link_handle);
+        }
+
+        self.contacts.retain(|contact| {
+            contact.body_handle != body_handle && contact.other_body_handle != body_handle
+        });
+        self.broadphase.remove(body_handle);
+
+        Some(body)
+    }
+
+    pub fn add_force_generator(
+        &mut self,
+        force_generator: impl ForceGenerator + 'static,
+    ) -> ForceGeneratorHandle {
+        self.force_generators.insert(Box::new(force_generator))
+    }
+
+    /// Adds a closure as a force generator.
+    /// It can be registered for bodies like any other generator.
+    pub fn add_force<F>(&mut self, force: F) -> ForceGeneratorHandle
+    where
+        F: FnMut(&ForceContext, &mut Body) + 'static,
+    {
+        self.add_force_generator(FnForce(force))
+    }
+
+    /// Removes a force generator along with its registrations.
+    pub fn remove_force_generator(
+        &mut self,
+        generator_handle: ForceGeneratorHandle,
+    ) -> Option<Box<dyn ForceGenerator>> {
+        self.registrations
+            .retain(|registration| registration.generator_handle != generator_handle);
+        self.global_registrations
+            .retain(|registration| registration.generator_handle != generator_handle);
+        self.force_generators.remove(generator_handle)
+    }
+
+    /// Applies a force generator to a body on every tick.
+    /// Registering the same pair more than once has no effect.
+    pub fn register_force(
+        &mut self,
+        generator_handle: ForceGeneratorHandle,
+        body_handle: BodyHandle,
+    ) {
+        let already_registered = self.registrations.iter().any(|registration| {
+            registration.generator_handle == generator_handle
+                && registration.bodies.contains(&body_handle)
+        });
+        if already_registered {
+            return;
+        }
+
+        match self
+            .registrations
+            .iter_mut()
+            .find(|registration| registration.generator_handle == generator_handle)
+        {
+            Some(registration) => registration.bodies.push(body_handle),
+            None => self
+                .registrations
+                .push(ForceRegistration::new(generator_handle, vec![body_handle])),
+        }
+    }
+
+    /// Stops applying a force generator to a body.
+    pub fn unregister_force(
+        &mut self,
+        generator_handle: ForceGeneratorHandle,
+        body_handle: BodyHandle,
+    ) {
+        for registration in self
+            .registrations
+            .iter_mut()
+            .filter(|registration| registration.generator_handle == generator_handle)
+        {
+            registration
+                .bodies
+                .retain(|registered_handle| *registered_handle != body_handle);
+        }
+        self.registrations.retain(|registration| {
+            registration.generator_handle != generator_handle || !registration.bodies.is_empty()
+        });
+    }
+
+    /// Applies a force generator to every dynamic body on every tick,
+    /// including bodies added later.
+    pub fn register_global_force(&mut self, generator_handle: ForceGeneratorHandle) {
+        self.register_global_force_filtered(generator_handle, CollisionFilter::default());
+    }
+
+    /// Applies a force generator to every dynamic body that the
+    /// collision filter can interact with, such as the bodies on a layer.
+    ///
+    /// Registering the same generator again replaces its filter.
+    pub fn register_global_force_filtered(
+        &mut self,
+        generator_handle: ForceGeneratorHandle,
+        collision_filter: CollisionFilter,
+    ) {
+        match self
+            .global_registrations
+            .iter_mut()
+            .find(|registration| registration.generator_handle == generator_handle)
+        {
+            Some(registration) => registration.collision_filter = collision_filter,
+            None => self.global_registrations.push(GlobalForceRegistration::new(
+                generator_handle,
+                collision_filter,
+            )),
+        }
+    }
+
+    /// Stops applying a force generator to every body.
+    /// Bodies registered for the generator individually are unaffected.
+    pub fn unregister_global_force(&mut self, generator_handle: ForceGeneratorHandle) {
+        self.global_registrations
+            .retain(|registration| registration.generator_handle != generator_handle);
+    }
+
+    /// The bodies a force generator applies to, whether registered individually or globally.
+    pub fn registered_bodies(&self, generator_handle: ForceGeneratorHandle) -> Vec<BodyHandle> {
+        let mut registered_bodies = self
+            .registrations
+            .iter()
+            .filter(|registration| registration.generator_handle == generator_handle)
+            .flat_map(|registration| registration.bodies.iter().copied())
+            .collect::<Vec<_>>();
+
+        let global_registrations = self
+            .global_registrations
+            .iter()
+            .filter(|registration| registration.generator_handle == generator_handle)
+            .collect::<Vec<_>>();
+        if global_registrations.is_empty() {
+            return registered_bodies;
+        }
+
+        let individually_registered = registered_bodies.iter().copied().collect::<BTreeSet<_>>();
+        registered_bodies.extend(
+            self.bodies
+                .iter()
+                .filter(|(body_handle, body)| {
+                    !individually_registered.contains(body_handle)
+                        && global_registrations
+                            .iter()
+                            .any(|registration| registration.applies_to(body))
+                })
+                .map(|(body_handle, _)| body_handle),
+        );
+        registered_bodies
+    }
+
+    pub fn add_contact_generator(
+        &mut self,
+        contact_generator: impl ContactGenerator + 'static,
+    ) -> ContactGeneratorHandle {
+        self.contact_generators.insert(Box::new(contact_generator))
+    }
+
+    pub fn remove_contact_generator(
+        &mut self,
+        generator_handle: ContactGeneratorHandle,
+    ) -> Option<Box<dyn ContactGenerator>> {
+        self.contact_generators.remove(generator_handle)
+    }
+
+    pub fn add_link(&mut self, link: Cable) -> LinkHandle {
+        self.links.insert(link)
+    }
+
+    pub fn remove_link(&mut self, link_handle: LinkHandle) -> Option<Cable> {
+        self.links.remove(link_handle)
+    }
+
+    pub fn add_force_field(&mut self, force_field: ForceField) -> ForceFieldHandle {
+        self.force_fields.insert(force_field)
+    }
+
+    pub fn remove_force_field(&mut self, field_handle: ForceFieldHandle) -> Option<ForceField> {
+        self.force_fields.remove(field_handle)
+    }
+
+    pub fn add_sensor(&mut self, sensor: Sensor) -> SensorHandle {
+        self.sensors.insert(sensor)
+    }
+
+    pub fn remove_sensor(&mut self, sensor_handle: SensorHandle) -> Option<Sensor> {
+        self.sensors.remove(sensor_handle)
+    }
+
     pub fn tick(&mut self, duration: Real) {
-        for registration in self.registrations.iter() {
-            let force_generator = match self.force_generators.get(registration.generator_handle) {
+        self.contact_events.clear();
+        self.sensor_events.clear();
+
+        let context = ForceContext {
+            duration,
+            elapsed: self.elapsed,
+            tick: self.tick_count,
+            boundary: self.boundary,
+        };
+
+        let applications = self
+            .force_generators
+            .iter()
+            .map(|(generator_handle, _)| {
+                (generator_handle, self.registered_bodies(generator_handle))
+            })
+            .collect::<Vec<_>>();
+
+        for (generator_handle, registered_bodies) in applications.iter() {
+            if let Some(force_generator) = self.force_generators.get_mut(*generator_handle) {
+                force_generator.update(&context, &self.bodies, registered_bodies);
+            }
+        }
+
+        for (generator_handle, registered_bodies) in applications.iter() {
+            let force_generator = match self.force_generators.get_mut(*generator_handle) {
                 Some(force_generator) => force_generator,
                 None => continue,
             };
 
-            for body_handle in registration.bodies.iter() {
-                (*force_generator).apply(duration, *body_handle, &mut self.bodies);
+            for body_handle in registered_bodies.iter() {
+                force_generator.apply(&context, *body_handle, &mut self.bodies);
             }
         }
 
+        self.apply_force_fields(&context);
+
         for (_index, body) in self.bodies.iter_mut() {
             body.integrate(duration);
         }
+
+        if let Some(boundary) = self.boundary {
+            // Static and kinematic bodies stay where they were placed
+            for (_index, body) in self.bodies.iter_mut() {
+                if body.body_type == BodyType::Dynamic {
+                    body.position = boundary.wrap(body.position);
+                }
+            }
+        }
+
+        self.generate_contacts();
+        self.contact_resolver
+            .resolve_contacts(&mut self.contacts, duration, &mut self.bodies);
+        self.record_contact_events();
+
+        self.update_broadphase();
+        self.update_sensors();
+
+        self.elapsed += duration;
+        self.tick_count += 1;
+    }
+
+    /// The total simulated time in seconds
+    pub fn elapsed(&self) -> Real {
+        self.elapsed
+    }
+
+    /// The number of ticks simulated so far
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
+    /// Takes the contact events recorded during the last tick.
+    ///
+    /// Events are discarded at the start of the next tick,
+    /// so they should be drained after every call to `tick`.
+    pub fn drain_contact_events(&mut self) -> std::vec::Drain<'_, ContactEvent> {
+        self.contact_events.drain(..)
+    }
+
+    /// Checks whether two bodies are allowed to interact, according to
+    /// their collision filters and the world's pair filter.
+    pub fn can_collide(&self, body_handle: BodyHandle, other_body_handle: BodyHandle) -> bool {
+        can_collide(
+            &self.bodies,
+            self.pair_filter.as_ref(),
+            body_handle,
+            other_body_handle,
+        )
+    }
+
+    fn generate_contacts(&mut self) {
+        self.contacts.clear();
+
+        let mut limit = self.max_contacts.unwrap_or(u32::MAX);
+        let mut generated = Vec::new();
+        // Links are constraints rather than collisions, so collision filters don't apply to them
+        let links = self
+            .links
+            .iter()
+            .map(|(_, link)| (link as &dyn ContactGenerator, false));
+        let contact_generators = self
+            .contact_generators
+            .iter()
+            .map(|(_, contact_generator)| (contact_generator.as_ref(), true));
+        for (contact_generator, filtered) in links.chain(contact_generators) {
+            if limit == 0 {
+                break;
+            }
+
+            contact_generator.add_contact(&self.bodies, &mut generated, limit);
+
+            // Discard contacts between bodies that aren't allowed to interact
+            let bodies = &self.bodies;
+            let pair_filter = self.pair_filter.as_ref();
+            let accepted = generated.drain(..).filter(|contact| {
+                !filtered
+                    || can_collide(
+                        bodies,
+                        pair_filter,
+                        contact.body_handle,
+                        contact.other_body_handle,
+                    )
+            });
+            let count_before = self.contacts.len();
+            self.contacts.extend(accepted.take(limit as usize));
+            limit -= (self.contacts.len() - count_before) as u32;
+        }
+    }
+
+    /// Takes the sensor events recorded during the last tick.
+    ///
+    /// Events are discarded at the start of the next tick,
+    /// so they should be drained after every call to `tick`.
+    pub fn drain_sensor_events(&mut self) -> std::vec::Drain<'_, SensorEvent> {
+        self.sensor_events.drain(..)
+    }
+
+    /// Applies each force field to the bodies inside it.
+    fn apply_force_fields(&mut self, context: &ForceContext) {
+        if self.force_fields.is_empty() {
+            return;
+        }
+
+        // Bodies may have been added or moved since the end of the last tick
+        self.update_broadphase();
+
+        let bodies = &mut self.bodies;
+        let broadphase = &self.broadphase;
+        for (_field_handle, force_field) in self.force_fields.iter_mut() {
+            let mut inside = Vec::new();
+            let mut detect = |body_handle| {
+                if bodies
+                    .get(body_handle)
+                    .is_some_and(|body| force_field.affects(body))
+                {
+                    inside.push(body_handle);
+                }
+            };
+            match force_field.volume.aabb() {
+                Some(aabb) => broadphase.query_aabb(&aabb, detect),
+                None => bodies
+                    .iter()
+                    .for_each(|(body_handle, _)| detect(body_handle)),
+            }
+            inside.sort();
+
+            force_field.generator.update(context, bodies, &inside);
+            for body_handle in inside {
+                force_field.apply(context, body_handle, bodies);
+            }
+        }
+    }
+
+    /// Finds the bodies inside each sensor, recording any that entered or exited.
+    fn update_sensors(&mut self) {
+        let bodies = &self.bodies;
+        let broadphase = &self.broadphase;
+        for (sensor_handle, sensor) in self.sensors.iter_mut() {
+            let mut occupants = BTreeSet::new();
+            let mut detect = |body_handle| {
+                let body = match bodies.get(body_handle) {
+                    Some(body) => body,
+                    None => return,
+                };
+                if sensor.collision_filter.can_interact(&body.collision_filter)
+                    && sensor.volume.intersects_shape(&body.shape, body.position)
+                {
+                    occupants.insert(body_handle);
+                }
+            };
+            match sensor.volume.aabb() {
+                Some(aabb) => broadphase.query_aabb(&aabb, detect),
+                None => bodies
+                    .iter()
+                    .for_each(|(body_handle, _)| detect(body_handle)),
+            }
+
+            let (entered, exited) = sensor.set_occupants(occupants);
+            let events = entered
+                .into_iter()
+                .map(|body_handle| (SensorEventKind::Entered, body_handle))
+                .chain(
+                    exited
+                        .into_iter()
+                        .map(|body_handle| (SensorEventKind::Exited, body_handle)),
+                )
+                .map(|(kind, body_handle)| SensorEvent {
+                    kind,
+                    sensor_handle,
+                    body_handle,
+                });
+            self.sensor_events.extend(events);
+        }
+    }
+
+    /// Compares this tick's contacts against the last tick's to record
+    /// which pairs of bodies started, stayed or stopped touching.
+    fn record_contact_events(&mut self) {
+        let mut touching = BTreeMap::new();
+        let impulses = self.contact_resolver.impulses.iter().copied();
+        for (contact, impulse) in self.contacts.iter().zip(impulses) {
+            touching
+                .entry(body_pair(contact.body_handle, contact.other_body_handle))
+                .and_modify(|event: &mut ContactEvent| event.impulse += impulse)
+                .or_insert(ContactEvent {
+                    kind: ContactEventKind::Started,
+                    body_handle: contact.body_handle,
+                    other_body_handle: contact.other_body_handle,
+                    point: contact.point,
+                    normal: contact.normal,
+                    impulse,
+                });
+        }
+
+        for (pair, event) in touching.iter_mut() {
+            if self.touching.contains_key(pair) {
+                event.kind = ContactEventKind::Persisted;
+            }
+            self.contact_events.push(*event);
+        }
+
+        for (pair, event) in self.touching.iter() {
+            if !touching.contains_key(pair) {
+                self.contact_events.push(ContactEvent {
+                    kind: ContactEventKind::Ended,
+                    impulse: 0.0,
+                    ..*event
+                });
+            }
+        }
+
+        self.touching = touching;
+    }
+
+    /// Refreshes the spatial structure used to accelerate queries.
+    ///
+    /// This happens automatically at the end of every tick, and bodies added or
+    /// removed through the world are tracked straight away. It must be called
+    /// manually for queries to see bodies that were inserted into or moved
+    /// through `bodies` directly since.
+    pub fn update_broadphase(&mut self) {
+        self.broadphase.update(&self.bodies);
+    }
+
+    /// Finds the bodies overlapping an axis-aligned box.
+    pub fn bodies_in_aabb(&self, aabb: &Aabb, filter: QueryFilter) -> Vec<BodyHandle> {
+        let mut body_handles = Vec::new();
+        self.broadphase.query_aabb(aabb, |body_handle| {
+            let body = match self.bodies.get(body_handle) {
+                Some(body) => body,
+                None => return,
+            };
+            if filter.accepts(body_handle, body) && body.shape.intersects_aabb(body.position, aabb)
+            {
+                body_handles.push(body_handle);
+            }
+        });
+        body_handles
+    }
+
+    /// Finds the bodies overlapping a sphere.
+    pub fn bodies_in_sphere(
+        &self,
+        center: Vector3,
+        radius: Real,
+        filter: QueryFilter,
+    ) -> Vec<BodyHandle> {
+        let mut body_handles = Vec::new();
+        self.broadphase
+            .query_aabb(&Aabb::from_sphere(center, radius), |body_handle| {
+                let body = match self.bodies.get(body_handle) {
+                    Some(body) => body,
+                    None => return,
+                };
+                if filter.accepts(body_handle, body)
+                    && body.shape.distance_to_point(body.position, center) <= radius
+                {
+                    body_handles.push(body_handle);
+                }
+            });
+        body_handles
+    }
+
+    /// Finds up to `count` bodies closest to a point, nearest first.
+    pub fn nearest_bodies(
+        &self,
+        point: Vector3,
+        count: usize,
+        filter: QueryFilter,
+    ) -> Vec<BodyHandle> {
+        self.broadphase.query_nearest(point, count, |body_handle| {
+            let body = self.bodies.get(body_handle)?;
+            if !filter.accepts(body_handle, body) {
+                return None;
+            }
+            Some(body.shape.distance_to_point(body.position, point))
+        })
+    }
+
+    /// Finds the closest body hit by a ray.
+    pub fn raycast(
+        &self,
+        origin: Vector3,
+        direction: Vector3,
+        max_distance: Real,
+        filter: QueryFilter,
+    ) -> Option<RaycastHit> {
+        self.sphere_cast(origin, direction, 0.0, max_distance, filter)
+    }
+
+    /// Finds every body hit by a ray, sorted from closest to furthest.
+    pub fn raycast_all(
+        &self,
+        origin: Vector3,
+        direction: Vector3,
+        max_distance: Real,
+        filter: QueryFilter,
+    ) -> Vec<RaycastHit> {
+        let mut hits = self.cast(origin, direction, 0.0, max_distance, filter);
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        hits
+    }
+
+    /// Finds the first body hit by a sphere swept along a ray.
+    pub fn sphere_cast(
+        &self,
+        origin: Vector3,
+        direction: Vector3,
+        radius: Real,
+        max_distance: Real,
+        filter: QueryFilter,
+    ) -> Option<RaycastHit> {
+        self.cast(origin, direction, radius, max_distance, filter)
+            .into_iter()
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+
+    fn cast(
+        &self,
+        origin: Vector3,
+        direction: Vector3,
+        radius: Real,
+        max_distance: Real,
+        filter: QueryFilter,
+    ) -> Vec<RaycastHit> {
+        let direction = direction.normalize();
+        if direction.magnitude_squared() <= 0.0 {
+            return Vec::new();
+        }
+
+        let mut hits = Vec::new();
+        self.broadphase
+            .query_ray(origin, direction, radius, max_distance, |body_handle| {
+                let body = match self.bodies.get(body_handle) {
+                    Some(body) => body,
+                    None => return,
+                };
+                if !filter.accepts(body_handle, body) {
+                    return;
+                }
+                if let Some((distance, normal)) =
+                    body.shape
+                        .cast_sphere(body.position, origin, direction, radius, max_distance)
+                {
+                    hits.push(RaycastHit {
+                        body_handle,
+                        point: origin + direction * distance - normal * radius,
+                        normal,
+                        distance,
+                    });
+                }
+            });
+        hits
+    }
+}
+
+fn can_collide(
+    bodies: &BodySet,
+    pair_filter: Option<&PairFilter>,
+    body_handle: BodyHandle,
+    other_body_handle: BodyHandle,
+) -> bool {
+    let (body, other_body) = match (bodies.get(body_handle), bodies.get(other_body_handle)) {
+        (Some(body), Some(other_body)) => (body, other_body),
+        _ => return false,
+    };
+
+    if !body
+        .collision_filter
+        .can_interact(&other_body.collision_filter)
+    {
+        return false;
+    }
+
+    match pair_filter {
+        Some(pair_filter) => pair_filter(body_handle, body, other_body_handle, other_body),
+        None => true,
+    }
+}
+
+/// Orders a pair of handles so that either ordering refers to the same pair.
+fn body_pair(body_handle: BodyHandle, other_body_handle: BodyHandle) -> (BodyHandle, BodyHandle) {
+    if body_handle <= other_body_handle {
+        (body_handle, other_body_handle)
+    } else {
+        (other_body_handle, body_handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Gravity, Link, Shape, Spring, Volume};
+
+    fn sphere(position: Vector3, radius: Real) -> Body {
+        Body::dynamic(1.0)
+            .position(position)
+            .shape(Shape::sphere(radius))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn queries_see_bodies_added_and_removed_between_ticks() {
+        let mut world = PhysicsWorld::default();
+        let body = world.add_body(sphere(Vector3::new(0.0, 0.0, 5.0), 1.0));
+
+        let hits = world.bodies_in_sphere(Vector3::zero(), 4.5, QueryFilter::default());
+        assert_eq!(hits, vec![body]);
+
+        let hit = world
+            .raycast(Vector3::zero(), Vector3::z(), 10.0, QueryFilter::default())
+            .unwrap();
+        assert_eq!(hit.body_handle, body);
+        assert!((hit.distance - 4.0).abs() < 1e-5);
+
+        world.remove_body(body);
+        assert!(world
+            .bodies_in_sphere(Vector3::zero(), 4.5, QueryFilter::default())
+            .is_empty());
+        assert!(world
+            .raycast(Vector3::zero(), Vector3::z(), 10.0, QueryFilter::default())
+            .is_none());
+    }
+
+    /// Two bodies 3 apart joined by a cable of length 2, either as
+    /// a link or as an ordinary contact generator.
+    fn cable_world(
+        filter: CollisionFilter,
+        as_link: bool,
+    ) -> (PhysicsWorld, BodyHandle, BodyHandle) {
+        let mut world = PhysicsWorld::default();
+        let body = |position| {
+            Body::dynamic(1.0)
+                .damping(1.0)
+                .position(position)
+                .collision_filter(filter)
+                .build()
+                .unwrap()
+        };
+        let body_handle = world.add_body(body(Vector3::zero()));
+        let other_body_handle = world.add_body(body(Vector3::new(3.0, 0.0, 0.0)));
+        let cable = Cable {
+            max_length: 2.0,
+            restitution: 0.0,
+            link: Link {
+                body_handle,
+                other_body_handle,
+            },
+        };
+        if as_link {
+            world.add_link(cable);
+        } else {
+            world.add_contact_generator(cable);
+        }
+        (world, body_handle, other_body_handle)
+    }
+
+    fn separation(world: &PhysicsWorld, body_handle: BodyHandle, other: BodyHandle) -> Real {
+        (world.bodies[body_handle].position - world.bodies[other].position).magnitude()
+    }
+
+    #[test]
+    fn raycast_all_sorts_hits_by_distance() {
+        let mut world = PhysicsWorld::default();
+        let far = world.add_body(sphere(Vector3::new(0.0, 0.0, 9.0), 1.0));
+        let near = world.add_body(sphere(Vector3::new(0.0, 0.0, 3.0), 1.0));
+        let middle = world.add_body(sphere(Vector3::new(0.0, 0.0, 6.0), 1.0));
+        world.add_body(sphere(Vector3::new(5.0, 0.0, 6.0), 1.0));
+
+        let hits = world.raycast_all(Vector3::zero(), Vector3::z(), 20.0, QueryFilter::default());
+        let order = hits.iter().map(|hit| hit.body_handle).collect::<Vec<_>>();
+        assert_eq!(order, vec![near, middle, far]);
+        for (hit, distance) in hits.iter().zip([2.0, 5.0, 8.0].iter()) {
+            assert!((hit.distance - distance).abs() < 1e-5);
+            assert!((hit.normal - Vector3::new(0.0, 0.0, -1.0)).magnitude() < 1e-5);
+        }
+
+        // Bodies beyond the maximum distance are left out
+        let hits = world.raycast_all(Vector3::zero(), Vector3::z(), 6.0, QueryFilter::default());
+        assert_eq!(hits.len(), 2);
+    }
+
+    #[test]
+    fn sphere_cast_hits_spheres_and_planes() {
+        let mut world = PhysicsWorld::default();
+        let ball = world.add_body(sphere(Vector3::new(0.0, 0.0, 10.0), 1.0));
+        let ground = world.add_body(
+            Body::fixed()
+                .shape(Shape::plane(Vector3::y()))
+                .build()
+                .unwrap(),
+        );
+
+        // The swept sphere touches the ball when their centers are two radii apart
+        let hit = world
+            .sphere_cast(
+                Vector3::new(0.0, 5.0, 0.0),
+                Vector3::new(0.0, -5.0, 10.0),
+                1.0,
+                20.0,
+                QueryFilter::default(),
+            )
+            .unwrap();
+        assert_eq!(hit.body_handle, ground);
+        let direction = Vector3::new(0.0, -1.0, 2.0).normalize();
+        assert!((hit.distance - 4.0 / -direction.y).abs() < 1e-4);
+        assert!(hit.point.y.abs() < 1e-4);
+        assert!((hit.normal - Vector3::y()).magnitude() < 1e-5);
+
+        let hit = world
+            .sphere_cast(
+                Vector3::new(0.0, 1.5, 0.0),
+                Vector3::z(),
+                1.0,
+                20.0,
+                QueryFilter::default(),
+            )
+            .unwrap();
+        assert_eq!(hit.body_handle, ball);
+        // The ray passes 1.5 from the ball's center, within the combined radius of 2
+        let expected = 10.0 - (4.0 as Real - 2.25).sqrt();
+        assert!((hit.distance - expected).abs() < 1e-4);
+        assert!(((hit.point - world.bodies[ball].position).magnitude() - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rays_starting_inside_a_shape_hit_immediately() {
+        let mut world = PhysicsWorld::default();
+        let ball = world.add_body(sphere(Vector3::new(0.0, 0.0, 0.5), 1.0));
+
+        let hit = world
+            .raycast(Vector3::zero(), Vector3::z(), 10.0, QueryFilter::default())
+            .unwrap();
+        assert_eq!(hit.body_handle, ball);
+        assert_eq!(hit.distance, 0.0);
+
+        // A sphere already overlapping a plane hits it at once too
+        let ground = world.add_body(
+            Body::fixed()
+                .position(Vector3::new(0.0, -10.0, 0.0))
+                .shape(Shape::plane(Vector3::y()))
+                .build()
+                .unwrap(),
+        );
+        let hit = world
+            .sphere_cast(
+                Vector3::new(0.0, -9.5, 0.0),
+                Vector3::x(),
+                1.0,
+                10.0,
+                QueryFilter::default(),
+            )
+            .unwrap();
+        assert_eq!(hit.body_handle, ground);
+        assert_eq!(hit.distance, 0.0);
+    }
+
+    #[test]
+    fn links_ignore_collision_filters() {
+        // Bodies sharing a negative group never collide, but their cable still holds
+        let (mut world, body_handle, other_body_handle) =
+            cable_world(CollisionFilter::default().with_group(-1), true);
+        world.pair_filter = Some(Box::new(|_, _, _, _| false));
+        world.tick(1.0 / 60.0);
+        assert_eq!(world.contacts.len(), 1);
+        assert!((separation(&world, body_handle, other_body_handle) - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn contact_generators_respect_collision_filters() {
+        let (mut world, _, _) = cable_world(CollisionFilter::default(), false);
+        world.tick(1.0 / 60.0);
+        assert_eq!(world.contacts.len(), 1);
+
+        let (mut world, body_handle, other_body_handle) =
+            cable_world(CollisionFilter::default().with_group(-1), false);
+        world.tick(1.0 / 60.0);
+        assert!(world.contacts.is_empty());
+        assert!(world.drain_contact_events().next().is_none());
+        assert!((separation(&world, body_handle, other_body_handle) - 3.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn pair_filter_discards_contacts() {
+        let (mut world, body_handle, _) = cable_world(CollisionFilter::default(), false);
+        world.pair_filter = Some(Box::new(move |first, _, second, _| {
+            first != body_handle && second != body_handle
+        }));
+        world.tick(1.0 / 60.0);
+        assert!(world.contacts.is_empty());
+    }
+
+    #[test]
+    fn contact_events_start_persist_and_end() {
+        let (mut world, body_handle, other_body_handle) =
+            cable_world(CollisionFilter::default(), true);
+        let pull = world.add_force(|_, body| body.add_force(&Vector3::new(50.0, 0.0, 0.0)));
+        world.register_force(pull, other_body_handle);
+
+        let tick = |world: &mut PhysicsWorld| {
+            world.tick(1.0 / 60.0);
+            world
+                .drain_contact_events()
+                .map(|event| (event.kind, event.body_handle, event.other_body_handle))
+                .collect::<Vec<_>>()
+        };
+
+        let events = tick(&mut world);
+        assert_eq!(
+            events,
+            vec![(ContactEventKind::Started, body_handle, other_body_handle)]
+        );
+
+        let events = tick(&mut world);
+        assert_eq!(
+            events,
+            vec![(ContactEventKind::Persisted, body_handle, other_body_handle)]
+        );
+
+        // Slackening the cable ends the contact, after which nothing more is reported
+        world.unregister_force(pull, other_body_handle);
+        world.bodies[body_handle].velocity = Vector3::new(5.0, 0.0, 0.0);
+        world.bodies[other_body_handle].velocity = Vector3::new(-5.0, 0.0, 0.0);
+        let events = tick(&mut world);
+        assert_eq!(
+            events,
+            vec![(ContactEventKind::Ended, body_handle, other_body_handle)]
+        );
+        assert!(tick(&mut world).is_empty());
+    }
+
+    #[test]
+    fn sensor_reports_bodies_entering_and_exiting() {
+        let mut world = PhysicsWorld::default();
+        let sensor = world.add_sensor(Sensor::new(Volume::sphere(Vector3::zero(), 1.0)));
+        let body = world.add_body(
+            Body::dynamic(1.0)
+                .damping(1.0)
+                .position(Vector3::new(-3.0, 0.0, 0.0))
+                .velocity(Vector3::new(6.0, 0.0, 0.0))
+                .shape(Shape::sphere(0.25))
+                .build()
+                .unwrap(),
+        );
+
+        let mut events = Vec::new();
+        for _ in 0..60 {
+            world.tick(1.0 / 60.0);
+            events.extend(
+                world
+                    .drain_sensor_events()
+                    .map(|event| (event.kind, event.sensor_handle, event.body_handle)),
+            );
+
+            let inside = world.bodies[body].position.magnitude() <= 1.25;
+            assert_eq!(world.sensors[sensor].contains(body), inside);
+        }
+        assert_eq!(
+            events,
+            vec![
+                (SensorEventKind::Entered, sensor, body),
+                (SensorEventKind::Exited, sensor, body),
+            ]
+        );
+
+        // Removing a body inside the sensor reports it leaving
+        world.bodies[body].position = Vector3::zero();
+        world.bodies[body].velocity = Vector3::zero();
+        world.tick(1.0 / 60.0);
+        assert_eq!(world.drain_sensor_events().count(), 1);
+        world.remove_body(body);
+        world.tick(1.0 / 60.0);
+        let events = world.drain_sensor_events().collect::<Vec<_>>();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, SensorEventKind::Exited);
+    }
+
+    #[test]
+    fn removing_a_body_cascades_through_everything_that_uses_it() {
+        let (mut world, body_handle, other_body_handle) =
+            cable_world(CollisionFilter::default(), true);
+        let gravity = world.add_force_generator(Gravity::default());
+        world.register_force(gravity, body_handle);
+        world.register_force(gravity, other_body_handle);
+        let spring = world.add_force_generator(Spring {
+            end_body_handle: other_body_handle,
+            spring_constant: 1.0,
+            rest_length: 1.0,
+        });
+        world.register_force(spring, body_handle);
+
+        world.tick(1.0 / 60.0);
+        assert_eq!(world.contacts.len(), 1);
+
+        assert!(world.remove_body(other_body_handle).is_some());
+        assert!(world.remove_body(other_body_handle).is_none());
+
+        assert!(!world.force_generators.contains(spring));
+        assert!(world
+            .registrations
+            .iter()
+            .all(|registration| registration.generator_handle != spring));
+        assert_eq!(world.registered_bodies(gravity), vec![body_handle]);
+        assert!(world.links.is_empty());
+        assert!(world.contacts.is_empty());
+
+        // The remaining body carries on without the removed one
+        world.tick(1.0 / 60.0);
+        assert!(world.contacts.is_empty());
+        assert!(world.bodies[body_handle].velocity.y < 0.0);
+    }
+
+    #[test]
+    fn unregistering_keeps_other_registrations() {
+        let mut world = PhysicsWorld::default();
+        let body_handle = world.add_body(Body::dynamic(1.0).build().unwrap());
+        let gravity = world.add_force_generator(Gravity::default());
+        let other_gravity = world.add_force_generator(Gravity::default());
+        world.register_force(gravity, body_handle);
+
+        // An empty registration added directly belongs to another generator
+        world
+            .registrations
+            .push(ForceRegistration::new(other_gravity, Vec::new()));
+
+        world.unregister_force(gravity, body_handle);
+        assert!(world.registered_bodies(gravity).is_empty());
+        assert_eq!(world.registrations.len(), 1);
+        assert_eq!(world.registrations[0].generator_handle, other_gravity);
+    }
+
+    #[test]
+    fn boundary_only_wraps_dynamic_bodies() {
+        let mut world = PhysicsWorld {
+            boundary: Some(PeriodicBoundary::from_size(Vector3::new(10.0, 10.0, 10.0))),
+            ..PhysicsWorld::default()
+        };
+        let outside = Vector3::new(12.0, 5.0, 5.0);
+        let dynamic = world.add_body(Body::dynamic(1.0).position(outside).build().unwrap());
+        let fixed = world.add_body(Body::fixed().position(outside).build().unwrap());
+        let kinematic = world.add_body(Body::kinematic().position(outside).build().unwrap());
+
+        world.tick(1.0 / 60.0);
+
+        assert!((world.bodies[dynamic].position - Vector3::new(2.0, 5.0, 5.0)).magnitude() < 1e-5);
+        assert_eq!(world.bodies[fixed].position, outside);
+        assert_eq!(world.bodies[kinematic].position, outside);
+    }
+
+    /// A world with earth gravity registered globally with the given filter.
+    fn global_gravity_world(filter: CollisionFilter) -> (PhysicsWorld, ForceGeneratorHandle) {
+        let mut world = PhysicsWorld::default();
+        let gravity = world.add_force_generator(Gravity::default());
+        world.register_global_force_filtered(gravity, filter);
+        (world, gravity)
+    }
+
+    /// How long the body would have been falling to reach its downward speed.
+    fn free_fall_time(world: &PhysicsWorld, body_handle: BodyHandle) -> Real {
+        -world.bodies[body_handle].velocity.y / Gravity::earth_gravity().y.abs()
+    }
+
+    #[test]
+    fn global_forces_apply_to_every_dynamic_body() {
+        let (mut world, gravity) = global_gravity_world(CollisionFilter::default());
+
+        // Bodies added after the registration are included, but immovable bodies aren't
+        let dynamic = world.add_body(Body::dynamic(2.0).damping(1.0).build().unwrap());
+        let fixed = world.add_body(Body::fixed().build().unwrap());
+        let kinematic = world.add_body(Body::kinematic().build().unwrap());
+        assert_eq!(world.registered_bodies(gravity), vec![dynamic]);
+
+        let duration = 0.125;
+        world.tick(duration);
+        assert!((free_fall_time(&world, dynamic) - duration).abs() < 1e-6);
+        assert_eq!(world.bodies[fixed].velocity, Vector3::zero());
+        assert_eq!(world.bodies[kinematic].velocity, Vector3::zero());
+    }
+
+    #[test]
+    fn global_forces_respect_layers() {
+        const DEBRIS: u32 = 1 << 1;
+        let (mut world, gravity) =
+            global_gravity_world(CollisionFilter::new(CollisionFilter::ALL, DEBRIS));
+        let on_layer = |memberships| {
+            Body::dynamic(1.0)
+                .damping(1.0)
+                .collision_filter(CollisionFilter::new(memberships, CollisionFilter::ALL))
+                .build()
+                .unwrap()
+        };
+        let debris = world.add_body(on_layer(DEBRIS));
+        let other = world.add_body(on_layer(1));
+        assert_eq!(world.registered_bodies(gravity), vec![debris]);
+
+        world.tick(0.125);
+        assert!(free_fall_time(&world, debris) > 0.0);
+        assert_eq!(world.bodies[other].velocity, Vector3::zero());
+    }
+
+    #[test]
+    fn bodies_registered_individually_and_globally_are_pulled_once() {
+        let (mut world, gravity) = global_gravity_world(CollisionFilter::default());
+        let body = world.add_body(Body::dynamic(1.0).damping(1.0).build().unwrap());
+        world.register_force(gravity, body);
+        assert_eq!(world.registered_bodies(gravity), vec![body]);
+
+        let duration = 0.125;
+        world.tick(duration);
+        assert!((free_fall_time(&world, body) - duration).abs() < 1e-6);
+    }
+
+    #[test]
+    fn gravity_scale_weakens_or_reverses_gravity() {
+        let (mut world, _) = global_gravity_world(CollisionFilter::default());
+        let scaled = |gravity_scale| {
+            Body::dynamic(1.0)
+                .damping(1.0)
+                .gravity_scale(gravity_scale)
+                .build()
+                .unwrap()
+        };
+        let weightless = world.add_body(scaled(0.0));
+        let floating = world.add_body(scaled(-1.0));
+        let halved = world.add_body(scaled(0.5));
+
+        let duration = 0.125;
+        world.tick(duration);
+        assert_eq!(world.bodies[weightless].velocity, Vector3::zero());
+        assert!((free_fall_time(&world, floating) + duration).abs() < 1e-6);
+        assert!((free_fall_time(&world, halved) - 0.5 * duration).abs() < 1e-6);
     }
 }